@@ -0,0 +1,294 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    num::NonZeroUsize,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use serde::Serialize;
+use sonicprobe_core::{
+    album::Album, analysis_tags::{analysis_tags, TagField}, audio_file::AudioFile,
+    builders::{album_builder::album_from_tracks, audio_file_builder::audio_file_form_stream_with}, decoder_from_stream,
+    flac_tags::write_flac_tags, open_decoder, raw::{RawPcmDecoder, RawPcmFormat}, replay_gain::ReplayGainPeak,
+    sonicprobe_error::SonicProbeError, AudioDecoder,
+};
+
+use crate::cli_args::{CliArgs, STDIN_PATH};
+
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "flac",
+    "wav",
+    "aif",
+    "aiff",
+    "aifc",
+    "mp3",
+    "m4a",
+    "mp4",
+    "aac",
+    "ogg",
+    "oga",
+    #[cfg(feature = "opus")]
+    "opus",
+];
+
+#[derive(Serialize)]
+pub struct FileReport {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<AudioFile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BatchSummary {
+    pub files: usize,
+    pub analysed: usize,
+    pub failed: usize,
+    pub non_compliant: usize,
+}
+
+#[derive(Serialize)]
+pub struct AlbumReport {
+    pub directory: String,
+    #[serde(flatten)]
+    pub album: Album,
+}
+
+#[derive(Serialize)]
+pub struct BatchReport<'a> {
+    pub files: &'a [FileReport],
+    pub albums: &'a [AlbumReport],
+    pub summary: BatchSummary,
+}
+
+impl BatchSummary {
+    pub fn new(reports: &[FileReport]) -> Self {
+        let failed = reports.iter().filter(|report| report.error.is_some()).count();
+        let non_compliant = reports
+            .iter()
+            .filter_map(|report| report.analysis.as_ref())
+            .filter(|analysis| !is_compliant(analysis))
+            .count();
+
+        Self {
+            files: reports.len(),
+            analysed: reports.len() - failed,
+            failed,
+            non_compliant,
+        }
+    }
+}
+
+pub fn is_compliant(analysis: &AudioFile) -> bool {
+    analysis.compliance.iter().all(|report| report.passed)
+}
+
+pub fn open_input(path: &str, raw_format: Option<RawPcmFormat>) -> Result<Box<dyn AudioDecoder>, SonicProbeError> {
+    match (raw_format, path == STDIN_PATH) {
+        (Some(format), true) => Ok(Box::new(RawPcmDecoder::new(io::stdin().lock(), format)?)),
+        (Some(format), false) => Ok(Box::new(RawPcmDecoder::open(path, format)?)),
+        (None, true) => decoder_from_stream(io::stdin().lock()),
+        (None, false) => open_decoder(path),
+    }
+}
+
+// The loudness series is only kept when it gets exported, the ReplayGain peak and the
+// compliance checks follow the options
+pub fn analyse_input(path: &str, args: &CliArgs) -> Result<AudioFile, SonicProbeError> {
+    let mut decoder = open_input(path, args.raw_format())?;
+    let mut audio_file = audio_file_form_stream_with(decoder.as_mut(), args.spectrum())?;
+
+    if !args.series() && args.csv_path().is_none() {
+        audio_file.loudness_series.clear();
+    }
+    audio_file.replay_gain = audio_file.replay_gain_with(args.replay_gain_peak());
+    audio_file.compliance = args.profiles().iter().map(|profile| profile.check(&audio_file)).collect();
+
+    Ok(audio_file)
+}
+
+// Directories are walked recursively for known audio extensions, patterns are expanded with glob.
+// Returns the files to analyse and whether more than a single plain file was requested
+pub fn expand_inputs(inputs: &[String]) -> (Vec<String>, bool) {
+    let mut files = Vec::new();
+    let mut is_batch = inputs.len() > 1;
+
+    for input in inputs {
+        let path = Path::new(input);
+
+        if path.is_dir() {
+            is_batch = true;
+            collect_audio_files(path, &mut files);
+        } else if input != STDIN_PATH && !path.exists() && is_glob_pattern(input) {
+            is_batch = true;
+            let matches_before = files.len();
+
+            for entry in glob::glob(input).into_iter().flatten().flatten() {
+                if entry.is_dir() {
+                    collect_audio_files(&entry, &mut files);
+                } else {
+                    files.push(entry.to_string_lossy().into_owned());
+                }
+            }
+
+            // Keep the pattern so that it gets reported instead of silently ignored
+            if files.len() == matches_before {
+                files.push(input.clone());
+            }
+        } else {
+            files.push(input.clone());
+        }
+    }
+
+    (files, is_batch)
+}
+
+fn is_glob_pattern(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+fn collect_audio_files(directory: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        files.push(directory.to_string_lossy().into_owned());
+        return;
+    };
+
+    let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            collect_audio_files(&path, files);
+        } else if has_audio_extension(&path) {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+}
+
+fn has_audio_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| AUDIO_EXTENSIONS.iter().any(|known| extension.eq_ignore_ascii_case(known)))
+}
+
+// Files are analysed on `jobs` workers, `on_report` is called in input order as soon as possible
+pub fn analyse_batch(paths: Vec<String>, args: &CliArgs, mut on_report: impl FnMut(&FileReport)) -> Vec<FileReport> {
+    let jobs = args
+        .jobs()
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get))
+        .min(paths.len())
+        .max(1);
+    let next_index = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut reports = Vec::with_capacity(paths.len());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (paths, next_index) = (&paths, &next_index);
+
+            scope.spawn(move || loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = paths.get(index) else {
+                    break;
+                };
+
+                let report = match analyse_input(path, args) {
+                    Ok(analysis) => FileReport { path: path.clone(), analysis: Some(analysis), error: None },
+                    Err(error) => FileReport { path: path.clone(), analysis: None, error: Some(error.message) },
+                };
+
+                if sender.send((index, report)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        for (index, report) in receiver {
+            pending.insert(index, report);
+
+            while let Some(report) = pending.remove(&reports.len()) {
+                on_report(&report);
+                reports.push(report);
+            }
+        }
+    });
+
+    reports
+}
+
+
+// Tracks sharing a parent directory are treated as an album once there are at least two of them
+pub fn albums_by_directory(reports: &[FileReport], replay_gain_peak: ReplayGainPeak) -> Vec<AlbumReport> {
+    let mut directories: Vec<String> = Vec::new();
+    let mut tracks: HashMap<String, Vec<&AudioFile>> = HashMap::new();
+
+    for report in reports {
+        let Some(analysis) = &report.analysis else {
+            continue;
+        };
+
+        let directory = parent_directory(&report.path);
+
+        tracks
+            .entry(directory.clone())
+            .or_insert_with(|| {
+                directories.push(directory);
+                Vec::new()
+            })
+            .push(analysis);
+    }
+
+    directories
+        .into_iter()
+        .filter_map(|directory| {
+            let album_tracks = tracks.get(&directory).filter(|album_tracks| album_tracks.len() > 1)?;
+            let album = album_from_tracks(album_tracks, replay_gain_peak).ok()?;
+
+            Some(AlbumReport { directory, album })
+        })
+        .collect()
+}
+
+// Each file also gets the album fields of its directory when it was part of one,
+// returns the files that could not be tagged along with the reason
+pub fn write_report_tags(reports: &[FileReport], albums: &[AlbumReport], fields: &[TagField]) -> Vec<(String, String)> {
+    reports
+        .iter()
+        .filter_map(|report| {
+            let analysis = report.analysis.as_ref()?;
+            let directory = parent_directory(&report.path);
+            let album = albums.iter().find(|album| album.directory == directory).map(|album| &album.album);
+
+            write_file_tags(&report.path, analysis, album, fields).err().map(|error| (report.path.clone(), error.message))
+        })
+        .collect()
+}
+
+pub fn write_file_tags(
+    path: &str,
+    analysis: &AudioFile,
+    album: Option<&Album>,
+    fields: &[TagField],
+) -> Result<(), SonicProbeError> {
+    if path == STDIN_PATH {
+        return Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "tags cannot be written to stdin".to_owned(),
+        });
+    }
+
+    write_flac_tags(path, &analysis_tags(analysis, album, fields))
+}
+
+fn parent_directory(path: &str) -> String {
+    Path::new(path).parent().map(|parent| parent.to_string_lossy().into_owned()).unwrap_or_default()
+}
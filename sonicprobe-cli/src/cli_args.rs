@@ -1,16 +1,63 @@
+use sonicprobe_core::{
+    analysis_tags::TagField, compliance::ComplianceProfile, raw::RawPcmFormat, replay_gain::ReplayGainPeak,
+    spectrum::SpectrumSettings,
+};
+
 use crate::OutputFormat;
 
+pub const STDIN_PATH: &str = "-";
+
 pub struct CliArgs {
-    pub(super) file_path: String,
+    pub(super) inputs: Vec<String>,
     pub(super) output_format: OutputFormat,
+    pub(super) raw_format: Option<RawPcmFormat>,
+    pub(super) jobs: Option<usize>,
+    pub(super) series: bool,
+    pub(super) csv_path: Option<String>,
+    pub(super) replay_gain_peak: ReplayGainPeak,
+    pub(super) write_tags: Vec<TagField>,
+    pub(super) profiles: Vec<ComplianceProfile>,
+    pub(super) spectrum: SpectrumSettings,
 }
 
 impl CliArgs {
-    pub const fn file_path(&self) -> &String {
-        &self.file_path
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
     }
 
     pub const fn output_format(&self) -> &OutputFormat {
         &self.output_format
     }
+
+    pub const fn raw_format(&self) -> Option<RawPcmFormat> {
+        self.raw_format
+    }
+
+    pub const fn jobs(&self) -> Option<usize> {
+        self.jobs
+    }
+
+    pub const fn series(&self) -> bool {
+        self.series
+    }
+
+    pub fn csv_path(&self) -> Option<&str> {
+        self.csv_path.as_deref()
+    }
+
+    pub const fn replay_gain_peak(&self) -> ReplayGainPeak {
+        self.replay_gain_peak
+    }
+
+    pub fn write_tags(&self) -> &[TagField] {
+        &self.write_tags
+    }
+
+    pub fn profiles(&self) -> &[ComplianceProfile] {
+        &self.profiles
+    }
+
+    pub const fn spectrum(&self) -> SpectrumSettings {
+        self.spectrum
+    }
 }
@@ -1,22 +1,28 @@
-use sonicprobe_core::sonicprobe_error::SonicProbeError;
+use std::{fs, str::FromStr};
+
+use sonicprobe_core::{
+    analysis_tags::TagField, compliance::ComplianceProfile, raw::RawPcmFormat, replay_gain::ReplayGainPeak, sonicprobe_error::SonicProbeError,
+    spectrum::{SpectrumSettings, SpectrumWindow},
+};
 
 use crate::{
-    cli_args::CliArgs, OutputFormat
+    cli_args::{CliArgs, STDIN_PATH}, OutputFormat
     
 };
 
+const VALUE_OPTIONS: &[&str] = &["--raw", "--channels", "--rate", "--jobs", "--csv", "--write-tags", "--profile", "--profile-file", "--fft-size", "--window"];
+
 pub fn cli_args_from_args_array(args: &[String]) -> Result<CliArgs, SonicProbeError> {
     assert!((args.len() >= 2), "No input file specified");
 
-    let input_file = args.get(1).map_or_else(
-        || {
-            Err(SonicProbeError {
-                location: format!("{}:{}", file!(), line!()),
-                message: "no input file provided".to_owned(),
-            })
-        },
-        Ok,
-    )?;
+    let inputs = positional_arguments(args);
+
+    if inputs.is_empty() {
+        return Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "no input file provided".to_owned(),
+        });
+    }
 
     let output_format: OutputFormat = {
         if args.len() >= 3
@@ -30,8 +36,148 @@ pub fn cli_args_from_args_array(args: &[String]) -> Result<CliArgs, SonicProbeEr
         }
     };
 
+    let raw_format = match option_value(args, "--raw")? {
+        Some(encoding) => {
+            let channels = parse_option(args, "--channels")?;
+            let sample_rate = parse_option(args, "--rate")?;
+            Some(RawPcmFormat::new(encoding, channels, sample_rate)?)
+        }
+        None => None,
+    };
+
+    let jobs = match option_value(args, "--jobs")? {
+        Some(_) => {
+            let jobs: usize = parse_option(args, "--jobs")?;
+            if jobs == 0 {
+                return Err(SonicProbeError {
+                    location: format!("{}:{}", file!(), line!()),
+                    message: "--jobs must be at least 1".to_owned(),
+                });
+            }
+            Some(jobs)
+        }
+        None => None,
+    };
+
+    let series = args.iter().any(|option| option.eq_ignore_ascii_case("--series"));
+    let csv_path = option_value(args, "--csv")?.cloned();
+
+    let replay_gain_peak = if args.iter().any(|option| option.eq_ignore_ascii_case("--rg-true-peak")) {
+        ReplayGainPeak::True
+    } else {
+        ReplayGainPeak::Sample
+    };
+
+    let write_tags = match option_value(args, "--write-tags")? {
+        Some(fields) => fields.split(',').map(|field| field.trim().parse()).collect::<Result<Vec<TagField>, _>>()?,
+        None => Vec::new(),
+    };
+
     Ok(CliArgs {
-        file_path: input_file.clone(),
+        inputs,
         output_format,
+        raw_format,
+        jobs,
+        series,
+        csv_path,
+        replay_gain_peak,
+        write_tags,
+        profiles: compliance_profiles(args)?,
+        spectrum: spectrum_settings(args)?,
+    })
+}
+
+fn spectrum_settings(args: &[String]) -> Result<SpectrumSettings, SonicProbeError> {
+    let defaults = SpectrumSettings::default();
+
+    let fft_size = match option_value(args, "--fft-size")? {
+        Some(_) => parse_option(args, "--fft-size")?,
+        None => defaults.fft_size(),
+    };
+    let window = match option_value(args, "--window")? {
+        Some(window) => window.parse::<SpectrumWindow>()?,
+        None => defaults.window(),
+    };
+
+    SpectrumSettings::new(fft_size, window)
+}
+
+// Profiles named with --profile are looked up among the built-in ones and those of
+// --profile-file, a file given alone has all of its profiles checked
+fn compliance_profiles(args: &[String]) -> Result<Vec<ComplianceProfile>, SonicProbeError> {
+    let user_profiles = match option_value(args, "--profile-file")? {
+        Some(path) => read_profiles(path)?,
+        None => Vec::new(),
+    };
+
+    let Some(names) = option_value(args, "--profile")? else {
+        return Ok(user_profiles);
+    };
+
+    let mut available = user_profiles;
+    available.extend(ComplianceProfile::builtin());
+
+    names
+        .split(',')
+        .map(|name| {
+            available.iter().find(|profile| profile.name.eq_ignore_ascii_case(name.trim())).cloned().ok_or_else(|| {
+                let known: Vec<&str> = available.iter().map(|profile| profile.name.as_str()).collect();
+                SonicProbeError {
+                    location: format!("{}:{}", file!(), line!()),
+                    message: format!("unknown profile '{name}', expected one of {}", known.join(", ")),
+                }
+            })
+        })
+        .collect()
+}
+
+// Either a single profile or a list of them
+fn read_profiles(path: &str) -> Result<Vec<ComplianceProfile>, SonicProbeError> {
+    let content = fs::read_to_string(path)?;
+
+    serde_json::from_str::<Vec<ComplianceProfile>>(&content)
+        .or_else(|_| serde_json::from_str::<ComplianceProfile>(&content).map(|profile| vec![profile]))
+        .map_err(|error| SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: format!("invalid profile file {path}: {error}"),
+        })
+}
+
+// Everything after the program name that is neither an option nor an option value
+fn positional_arguments(args: &[String]) -> Vec<String> {
+    let mut inputs = Vec::new();
+    let mut arguments = args.iter().skip(1);
+
+    while let Some(argument) = arguments.next() {
+        if VALUE_OPTIONS.iter().any(|option| argument.eq_ignore_ascii_case(option)) {
+            arguments.next();
+        } else if argument == STDIN_PATH || !argument.starts_with("--") {
+            inputs.push(argument.clone());
+        }
+    }
+
+    inputs
+}
+
+fn option_value<'a>(args: &'a [String], name: &str) -> Result<Option<&'a String>, SonicProbeError> {
+    let Some(position) = args.iter().position(|option| option.eq_ignore_ascii_case(name)) else {
+        return Ok(None);
+    };
+
+    args.get(position + 1).map(Some).ok_or_else(|| SonicProbeError {
+        location: format!("{}:{}", file!(), line!()),
+        message: format!("missing value for {name}"),
+    })
+}
+
+fn parse_option<T: FromStr>(args: &[String], name: &str) -> Result<T, SonicProbeError> {
+    let value = option_value(args, name)?.ok_or_else(|| SonicProbeError {
+        location: format!("{}:{}", file!(), line!()),
+        message: format!("{name} is required"),
+    })?;
+
+    value.parse().map_err(|_| SonicProbeError {
+        location: format!("{}:{}", file!(), line!()),
+        message: format!("invalid value '{value}' for {name}"),
     })
 }
@@ -0,0 +1,40 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use sonicprobe_core::{loudness::LoudnessPoint, sonicprobe_error::SonicProbeError};
+
+const SERIES_HEADER: &str = "time,momentary_lufs,peak_dbfs,true_peak_dbfs,short_term_lufs,short_term_peak_dbfs,short_term_true_peak_dbfs,psr_db";
+
+// Non finite values such as the loudness of digital silence are left empty
+pub fn write_series_csv(path: &str, series: &[LoudnessPoint]) -> Result<(), SonicProbeError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{SERIES_HEADER}")?;
+
+    for point in series {
+        writeln!(
+            writer,
+            "{:.1},{},{},{},{},{},{},{}",
+            point.time,
+            csv_value(point.momentary.get_value()),
+            csv_value(point.peak.get_value()),
+            csv_value(point.true_peak.get_value()),
+            point.short_term.map(|value| csv_value(value.get_value())).unwrap_or_default(),
+            point.short_term_peak.map(|value| csv_value(value.get_value())).unwrap_or_default(),
+            point.short_term_true_peak.map(|value| csv_value(value.get_value())).unwrap_or_default(),
+            point.psr.map(csv_value).unwrap_or_default(),
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn csv_value(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value:.2}")
+    } else {
+        String::new()
+    }
+}
@@ -1,16 +1,24 @@
 mod ui;
+mod batch;
 mod cli_args;
 mod cli_args_builder;
+mod export;
 mod output_format;
 
-use claxon::FlacReader;
 use std::{env, process};
 
-use sonicprobe_core::builders::audio_file_builder::audio_file_form_stream;
+// Analysis succeeded but at least one compliance profile was not met
+const NON_COMPLIANT_EXIT_CODE: i32 = 2;
+
+use crate::batch::{
+    albums_by_directory, analyse_batch, analyse_input, expand_inputs, is_compliant, write_file_tags, write_report_tags, BatchReport,
+    BatchSummary,
+};
 use crate::cli_args_builder::cli_args_from_args_array;
 use crate::cli_args::CliArgs;
+use crate::export::write_series_csv;
 use crate::output_format::OutputFormat;
-use crate::ui::print_file_details;
+use crate::ui::{file_details_report, print_album_summary, print_batch_error, print_batch_summary, print_file_details};
 
 fn main() {
     let cli_input: Vec<String> = env::args().collect();
@@ -23,25 +31,96 @@ fn main() {
         }
     };
 
-    let flac_details = match FlacReader::open(args.file_path()) {
-        Ok(stream) => audio_file_form_stream(stream),
-        Err(error) => {
-            println!("error while opening {} : {:?}", args.file_path(), error);
+    let (paths, is_batch) = expand_inputs(args.inputs());
+
+    if is_batch {
+        run_batch(paths, &args);
+    } else {
+        run_single(&paths[0], &args);
+    }
+}
+
+fn run_single(path: &str, args: &CliArgs) {
+    let mut audio_file = match analyse_input(path, args) {
+        Ok(audio_file) => audio_file,
+        Err(e) => {
+            println!("error while analysing {path} : {e:?}");
             process::exit(1);
         }
     };
 
-    match flac_details {
-        Ok(audio_file) => {
-            if *args.output_format() == OutputFormat::Json {
-                println!("{}", audio_file.to_json());
-            } else {
-                print_file_details(args.file_path(), &audio_file);
-            }
+    if let Some(csv_path) = args.csv_path() {
+        if let Err(e) = write_series_csv(csv_path, &audio_file.loudness_series) {
+            println!("error while writing {csv_path} : {e:?}");
+            process::exit(1);
         }
-        Err(e) => {
-            println!("{e:?}");
+    }
+
+    if !args.series() {
+        audio_file.loudness_series.clear();
+    }
+
+    if *args.output_format() == OutputFormat::Json {
+        println!("{}", audio_file.to_json());
+    } else {
+        print_file_details(path, &audio_file);
+    }
+
+    if !args.write_tags().is_empty() {
+        if let Err(e) = write_file_tags(path, &audio_file, None, args.write_tags()) {
+            eprintln!("error while writing tags to {path} : {}", e.message);
             process::exit(1);
         }
     }
+
+    if !is_compliant(&audio_file) {
+        process::exit(NON_COMPLIANT_EXIT_CODE);
+    }
+}
+
+fn run_batch(paths: Vec<String>, args: &CliArgs) {
+    let json = *args.output_format() == OutputFormat::Json;
+
+    if args.csv_path().is_some() {
+        println!("--csv can only be used with a single input file");
+        process::exit(1);
+    }
+
+    let reports = analyse_batch(paths, args, |report| {
+        if json {
+            return;
+        }
+
+        match &report.analysis {
+            Some(audio_file) => println!("{}", file_details_report(&report.path, audio_file)),
+            None => print_batch_error(report),
+        }
+    });
+
+    let albums = albums_by_directory(&reports, args.replay_gain_peak());
+
+    if json {
+        let batch = BatchReport { files: &reports, albums: &albums, summary: BatchSummary::new(&reports) };
+        match serde_json::to_string_pretty(&batch) {
+            Ok(value) => println!("{value}"),
+            Err(e) => println!("Error while serializing: {e:?}"),
+        }
+    } else {
+        albums.iter().for_each(print_album_summary);
+        print_batch_summary(&reports);
+    }
+
+    let tag_failures =
+        if args.write_tags().is_empty() { Vec::new() } else { write_report_tags(&reports, &albums, args.write_tags()) };
+    for (path, error) in &tag_failures {
+        eprintln!("error while writing tags to {path} : {error}");
+    }
+
+    if !tag_failures.is_empty() || reports.iter().any(|report| report.error.is_some()) {
+        process::exit(1);
+    }
+
+    if reports.iter().filter_map(|report| report.analysis.as_ref()).any(|analysis| !is_compliant(analysis)) {
+        process::exit(NON_COMPLIANT_EXIT_CODE);
+    }
 }
@@ -4,9 +4,18 @@ mod filesystem;
 mod table;
 mod section;
 
-use sonicprobe_core::audio_file::AudioFile;
+use sonicprobe_core::{
+    audio_file::AudioFile,
+    channel::Channel,
+    channel_layout::ChannelLabel,
+    compliance::{ComplianceCheck, ComplianceMetric, ComplianceReport},
+    replay_gain::ReplayGain,
+    spectrum::{Spectrum, SpectrumBand},
+};
 
 use crate::{
+    batch::{AlbumReport, BatchSummary, FileReport},
+    cli_args::STDIN_PATH,
     ui::{
         entry::Entry, filesystem::{filename_from_path, get_formatted_file_size}, section::Section, table::Table
     },
@@ -14,6 +23,10 @@ use crate::{
 
 
 
+const TABLE_COLUMNS: usize = 4;
+const SPECTRUM_FLOOR: f64 = -96.0;
+const SPECTRUM_BAR_WIDTH: usize = 32;
+
 fn seconds_to_minute_mark(duration: usize) -> String {
     let seconds = duration % 60;
     let minutes = (duration - seconds) / 60;
@@ -22,33 +35,244 @@ fn seconds_to_minute_mark(duration: usize) -> String {
 }
 
 pub fn print_file_details(filepath: &str, file: &AudioFile) {
-    let left = file.left;
-    let right = file.right;
-    let filename = filename_from_path(filepath).map_or_else(|| filepath.to_owned(), |value| value);
-    let formatted_size = get_formatted_file_size(filepath).unwrap_or_else(|_| "?".to_owned());
-
-    println!("{}", "=".repeat(70));
-    println!("{:^70}", "SONICPROBE - AUDIO ANALYSIS REPORT");
-    println!("{}\n", "=".repeat(70));
-
-    let file_details = Section::new("FILE DETAILS")
-        .add("Filename", Entry::from(filename))
-        .add("Size", Entry::from(formatted_size))
-        .add("Sample Count", Entry::from(file.samples_per_channel * usize::from(file.channels)))
+    println!("{}", file_details_report(filepath, file));
+}
+
+pub fn file_details_report(filepath: &str, file: &AudioFile) -> String {
+    let mut report = vec![
+        "=".repeat(70),
+        format!("{:^70}", "SONICPROBE - AUDIO ANALYSIS REPORT"),
+        format!("{}\n", "=".repeat(70)),
+    ];
+
+    let file_details = if filepath == STDIN_PATH {
+        Section::new("FILE DETAILS").add("Filename", Entry::from("stdin".to_owned()))
+    } else {
+        let filename = filename_from_path(filepath).unwrap_or_else(|| filepath.to_owned());
+        let formatted_size = get_formatted_file_size(filepath).unwrap_or_else(|_| "?".to_owned());
+
+        Section::new("FILE DETAILS").add("Filename", Entry::from(filename)).add("Size", Entry::from(formatted_size))
+    };
+
+    let file_details = file_details
+        .add("Sample Count", Entry::from(file.samples_per_channel * file.channel_count()))
         .add("Duration", Entry::from(seconds_to_minute_mark(file.duration)))
-        .add("Sample Rate", Entry::from(file.sample_rate.description().to_owned()))
-        .add("Bit Depth", Entry::from(file.depth.description().to_owned()))
-        .add("Bit depth usage", Entry::from_bit(file.true_depth))
+        .add("Sample Rate", Entry::from(file.sample_rate.description()))
+        .add("Bit Depth", Entry::from(file.depth.description()))
+        .add("Bit depth usage", Entry::from_depth_usage(file.true_depth))
+        .add("Lossy transcode", Entry::from(file.lossy_transcode.description()))
         .build();
 
-    let stereo_field_analisys = Section::new("STEREO FIELD ANALYSIS")
-        .add("Channels", Entry::from(file.channels as usize))
-        .add("RMS Balance (L/R)",Entry::from(file.rms_balance()))
-        .add("Stereo Correlation", Entry::from_percent(file.stereo_correlation * 100.0))
+    let layout: Vec<String> = file.channels.iter().map(|channel| channel.label().short_name()).collect();
+    let mut stereo_field_analisys = Section::new("STEREO FIELD ANALYSIS")
+        .add("Channels", Entry::from(file.channel_count()))
+        .add("Layout", Entry::from(layout.join(" ")))
+        .add("PLR", Entry::from_ratio(file.peak_to_loudness.plr))
+        .add("PSR (min)", Entry::from_ratio(file.peak_to_loudness.min_psr))
+        .add("PSR (median)", Entry::from_ratio(file.peak_to_loudness.median_psr));
+
+    for pair in &file.channel_pairs {
+        let pair_name = format!("{}/{}", pair.left.short_name(), pair.right.short_name());
+        stereo_field_analisys = stereo_field_analisys
+            .add(&format!("RMS Balance ({pair_name})"), Entry::from(pair.rms_balance))
+            .add(&format!("Correlation ({pair_name})"), Entry::from_percent(pair.correlation * 100.0));
+    }
+
+    report.push(file_details);
+
+    if !file.metadata.is_empty() {
+        report.push(metadata_section(file));
+    }
+
+    let loudness = Section::new("LOUDNESS")
+        .add("Integrated", Entry::from(file.integrated_loudness))
+        .add("Loudness Range", Entry::from(file.loudness_range))
+        .add("Dynamic Range", Entry::from(file.dr))
         .build();
 
-    let channels_details_table = Table::new(left, right)
-        .set_headers("CHANNEL ANALYSIS", "LEFT", "RIGHT")
+    let (track_gain, track_peak) = replay_gain_entries(file.replay_gain);
+    let replay_gain = Section::new("REPLAYGAIN").add("Track Gain", track_gain).add("Track Peak", track_peak).build();
+
+    report.push(loudness);
+    report.push(replay_gain);
+    report.extend(file.compliance.iter().map(compliance_section));
+    report.push(stereo_field_analisys.build());
+
+    for channels in file.channels.chunks(TABLE_COLUMNS) {
+        report.push(channels_details_table(channels));
+    }
+
+    if let Some(spectrum) = spectrum_section(&file.spectrum) {
+        report.push(spectrum);
+    }
+
+    report.join("\n")
+}
+
+pub fn print_batch_summary(reports: &[FileReport]) {
+    let summary = BatchSummary::new(reports);
+
+    let mut section = Section::new("BATCH SUMMARY")
+        .add("Files", Entry::from(summary.files))
+        .add("Analysed", Entry::from(summary.analysed))
+        .add("Failed", Entry::from(summary.failed));
+
+    if summary.non_compliant > 0 {
+        section = section.add("Non-compliant", Entry::from(summary.non_compliant));
+    }
+
+    for report in reports {
+        if let Some(error) = &report.error {
+            section = section.add("Error", Entry::from(format!("{} : {error}", report.path)));
+        }
+    }
+
+    println!("{}", section.build());
+}
+
+pub fn print_album_summary(report: &AlbumReport) {
+    let album = &report.album;
+    let correlation = album.correlation.map_or_else(|| Entry::from("--".to_owned()), |value| Entry::from_percent(value * 100.0));
+
+    let section = Section::new("ALBUM")
+        .add("Directory", Entry::from(report.directory.clone()))
+        .add("Tracks", Entry::from(album.tracks))
+        .add("Duration", Entry::from(seconds_to_minute_mark(album.duration)))
+        .add("Dynamic Range", Entry::from(album.dr))
+        .add("Peak Level", Entry::from(album.peak))
+        .add("True Peak", Entry::from(album.true_peak))
+        .add("RMS Level", Entry::from(album.rms))
+        .add("Correlation (L/R)", correlation)
+        .add("Integrated", Entry::from(album.integrated_loudness));
+
+    let (album_gain, album_peak) = replay_gain_entries(album.replay_gain);
+    let section = section.add("Album Gain", album_gain).add("Album Peak", album_peak).build();
+
+    println!("{section}");
+}
+
+// 1/3-octave bands averaged in power over the channels, the per channel spectrum is in the JSON output
+fn spectrum_section(spectrum: &[Spectrum]) -> Option<String> {
+    let bands: &[SpectrumBand] = &spectrum.first()?.bands;
+    if bands.is_empty() {
+        return None;
+    }
+
+    let mut section = Section::new("AVERAGE SPECTRUM (1/3 OCTAVE)");
+
+    for (index, band) in bands.iter().enumerate() {
+        let powers = spectrum.iter().filter_map(|channel| channel.bands.get(index)).map(|band| 10f64.powf(band.level / 10.0));
+        let (sum, count) = powers.fold((0.0, 0.0), |(sum, count), power| (sum + power, count + 1.0));
+        let level = 10.0 * (sum / count).log10();
+
+        section = section.add(&Spectrum::band_label(band.centre), Entry::from(format!("{} {} dBFS", spectrum_bar(level), format_level(level))));
+    }
+
+    Some(section.build())
+}
+
+// Half block resolution between the floor and full scale
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+#[allow(clippy::cast_precision_loss)]
+fn spectrum_bar(level: f64) -> String {
+    let fill = if level.is_finite() { (level - SPECTRUM_FLOOR) / -SPECTRUM_FLOOR } else { 0.0 };
+    let halves = (fill.clamp(0.0, 1.0) * (SPECTRUM_BAR_WIDTH * 2) as f64).round() as usize;
+
+    let mut bar = "█".repeat(halves / 2);
+    if halves % 2 == 1 {
+        bar.push('▌');
+    }
+    let padding = SPECTRUM_BAR_WIDTH - bar.chars().count();
+    bar.push_str(&" ".repeat(padding));
+
+    bar
+}
+
+fn compliance_section(report: &ComplianceReport) -> String {
+    let mut section = Section::new(&format!("COMPLIANCE - {}", report.profile));
+
+    for check in &report.checks {
+        section = section.add(compliance_label(check.metric), Entry::from(compliance_check(check)));
+    }
+
+    if let Some(note) = &report.note {
+        section = section.add("Note", Entry::from(note.clone()));
+    }
+
+    section.add("Verdict", Entry::from(verdict(report.passed).to_owned())).build()
+}
+
+const fn compliance_label(metric: ComplianceMetric) -> &'static str {
+    match metric {
+        ComplianceMetric::Integrated => "Integrated",
+        ComplianceMetric::TruePeak => "True Peak",
+        ComplianceMetric::LoudnessRange => "Loudness Range",
+        ComplianceMetric::ShortTerm => "Max Short-Term",
+    }
+}
+
+// e.g. "PASS  -23.4 LUFS (target -23.0, margin +0.1)"
+fn compliance_check(check: &ComplianceCheck) -> String {
+    let (unit, limit) = match check.metric {
+        ComplianceMetric::Integrated => ("LUFS", "target"),
+        ComplianceMetric::TruePeak => ("dBTP", "max"),
+        ComplianceMetric::LoudnessRange => ("LU", "max"),
+        ComplianceMetric::ShortTerm => ("LUFS", "max"),
+    };
+
+    format!(
+        "{}  {} {unit} ({limit} {:.1}, margin {})",
+        verdict(check.passed),
+        format_level(check.measured),
+        check.limit,
+        format_level(check.margin),
+    )
+}
+
+fn format_level(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value:+.1}")
+    } else if value > 0.0 {
+        "+inf".to_owned()
+    } else {
+        "-inf".to_owned()
+    }
+}
+
+const fn verdict(passed: bool) -> &'static str {
+    if passed { "PASS" } else { "FAIL" }
+}
+
+// Written as the REPLAYGAIN_* tag values, programs below the loudness gate have none
+fn replay_gain_entries(replay_gain: Option<ReplayGain>) -> (Entry, Entry) {
+    replay_gain.map_or_else(
+        || (Entry::from("--".to_owned()), Entry::from("--".to_owned())),
+        |value| (Entry::from(value.gain_tag()), Entry::from(value.peak_tag())),
+    )
+}
+
+pub fn print_batch_error(report: &FileReport) {
+    if let Some(error) = &report.error {
+        println!("error while analysing {} : {error}\n", report.path);
+    }
+}
+
+fn channel_header(label: ChannelLabel) -> String {
+    match label {
+        ChannelLabel::Mono => "MONO".to_owned(),
+        ChannelLabel::FrontLeft => "LEFT".to_owned(),
+        ChannelLabel::FrontRight => "RIGHT".to_owned(),
+        other => other.short_name(),
+    }
+}
+
+fn channels_details_table(channels: &[Channel]) -> String {
+    let headers: Vec<String> = channels.iter().map(|channel| channel_header(channel.label())).collect();
+    let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+
+    Table::new(channels.to_vec())
+        .set_headers("CHANNEL ANALYSIS", &headers)
         .add_section()
         .add("RMS Level", |c| Entry::from(c.rms()))
         .add("Peak Level", |c| Entry::from(c.peak()))
@@ -58,7 +282,8 @@ pub fn print_file_details(filepath: &str, file: &AudioFile) {
         .add("Zero Crossing Rate", |c| {
             Entry::from_hz(c.zero_crossing_rate())
         })
-        .add("Dynamic Range", |c| Entry::from(c.dr()))
+        .add("Dynamic Range", |c| Entry::from(c.dr_value()))
+        .add("RMS Range", |c| Entry::from(c.rms_range()))
         .add_section()
         .add("Clipping", |c| {
             Entry::from_percent(c.clipping_samples_ratio() * 100.0)
@@ -66,9 +291,21 @@ pub fn print_file_details(filepath: &str, file: &AudioFile) {
         .add("True Clipping", |c| {
             Entry::from_percent(c.true_clipping_samples_ratio() * 100.0)
         })
-        .build();
+        .build()
+}
+
+fn metadata_section(file: &AudioFile) -> String {
+    let mut section = Section::new("METADATA");
+
+    for tag in &file.metadata.tags {
+        section = section.add(&tag.key, Entry::from(tag.value.clone()));
+    }
+
+    for marker in &file.metadata.markers {
+        let position = usize::try_from(marker.position).unwrap_or(usize::MAX);
+        let timestamp = seconds_to_minute_mark(position / file.sample_rate.to_hz());
+        section = section.add(&format!("Marker {}", marker.id), Entry::from(format!("{timestamp} {}", marker.name)));
+    }
 
-    println!("{file_details}");
-    println!("{stereo_field_analisys}");
-    println!("{channels_details_table}");
+    section.build()
 }
@@ -1,4 +1,4 @@
-use sonicprobe_core::{decibel::Decibel, dynamic_range::DynamicRange};
+use sonicprobe_core::{bit_depth::DepthUsage, decibel::Decibel, dynamic_range::DynamicRange, loudness::{Loudness, LoudnessRange}};
 
 use crate::ui::audio::{format_hz, format_volt};
 
@@ -43,6 +43,24 @@ impl From<DynamicRange> for Entry {
     }
 }
 
+impl From<Loudness> for Entry {
+    fn from(obj: Loudness) -> Self {
+        Self {
+            value: obj.get_string_value(),
+            unit: Some(Loudness::get_unit()),
+        }
+    }
+}
+
+impl From<LoudnessRange> for Entry {
+    fn from(obj: LoudnessRange) -> Self {
+        Self {
+            value: obj.get_string_value(),
+            unit: Some(LoudnessRange::get_unit()),
+        }
+    }
+}
+
 impl Entry {
     pub fn formatted(self) -> String {
         match self.unit {
@@ -72,12 +90,39 @@ impl Entry {
         }
     }
 
+    // Peak to loudness ratios, "--" when the program is too quiet to measure
+    pub fn from_ratio(value: f64) -> Self {
+        if !value.is_finite() {
+            return Self::from("--".to_owned());
+        }
+
+        // Adding 0.0 turns a rounded -0.0 into 0.0
+        Self {
+            value: format!("{:.1}", (value * 10.0).round() / 10.0 + 0.0),
+            unit: Some(Decibel::get_unit()),
+        }
+    }
+
     pub fn from_bit(value: u8) -> Self {
         Self {
             value: format!("{value}"),
             unit: Some(String::from("bit")),
         }
     }
+
+    pub fn from_depth_usage(usage: DepthUsage) -> Self {
+        match usage {
+            DepthUsage::Integer(bits) => Self::from_bit(bits),
+            DepthUsage::QuantisedFloat(bits) => Self {
+                value: format!("{bits}"),
+                unit: Some(String::from("bit integer audio in float")),
+            },
+            DepthUsage::Mantissa(bits) => Self {
+                value: format!("{bits}"),
+                unit: Some(String::from("bit mantissa")),
+            },
+        }
+    }
 }
 
 pub fn format_percent(value: f64) -> String {
@@ -4,32 +4,32 @@ use crate::ui::entry::Entry;
 
 #[derive(Clone)]
 pub struct Table {
-    left: Channel,
-    right: Channel,
+    channels: Vec<Channel>,
     elements: Vec<String>,
 }
 
 impl Table {
-    pub fn new(left: Channel, right: Channel) -> Self {
+    pub fn new(channels: Vec<Channel>) -> Self {
+        let columns = channels.len();
+
         Self {
-            left,
-            right,
-            elements: vec![table_head()],
+            channels,
+            elements: vec![table_head(columns)],
         }
     }
 
     #[inline]
     pub fn add(mut self, title: &str, mapping_fn: fn(Channel) -> Entry) -> Self {
-        let left = mapping_fn(self.left);
-        let right = mapping_fn(self.right);
-        let new_row = table_row(title, &left.formatted(), &right.formatted());
+        let cells: Vec<String> = self.channels.iter().map(|channel| mapping_fn(*channel).formatted()).collect();
+        let new_row = table_row(title, &cells);
         self.elements.push(new_row);
 
         self
     }
 
-    pub fn set_headers(mut self, title: &str, left: &str, right: &str) -> Self {
-        let new_row = table_row(title, left, right);
+    pub fn set_headers(mut self, title: &str, headers: &[&str]) -> Self {
+        let cells: Vec<String> = headers.iter().map(|&header| header.to_owned()).collect();
+        let new_row = table_row(title, &cells);
         self.elements.push(new_row);
 
         self
@@ -37,29 +37,37 @@ impl Table {
 
     #[inline]
     pub fn add_section(mut self) -> Self {
-        self.elements.push(table_section());
+        self.elements.push(table_section(self.channels.len()));
 
         self
     }
 
     pub fn build(&mut self) -> String {
-        self.elements.push(table_footer());
+        self.elements.push(table_footer(self.channels.len()));
         self.elements.join("\n")
     }
 }
 
-fn table_head() -> String {
-    format!("┌{}┬{}┬{}┐", "─".repeat(26), "─".repeat(20), "─".repeat(20))
+fn table_line(columns: usize, left: &str, junction: &str, right: &str) -> String {
+    let cells = vec!["─".repeat(20); columns].join(junction);
+
+    format!("{left}{}{junction}{cells}{right}", "─".repeat(26))
 }
 
-fn table_footer() -> String {
-    format!("└{}┴{}┴{}┘", "─".repeat(26), "─".repeat(20), "─".repeat(20))
+fn table_head(columns: usize) -> String {
+    table_line(columns, "┌", "┬", "┐")
 }
 
-fn table_section() -> String {
-    format!("├{}┼{}┼{}┤", "─".repeat(26), "─".repeat(20), "─".repeat(20))
+fn table_footer(columns: usize) -> String {
+    table_line(columns, "└", "┴", "┘")
 }
 
-fn table_row(title: &str, left: &str, right: &str) -> String {
-    format!("│  {title:<22}  │    {left:>14}  │    {right:>14}  │")
+fn table_section(columns: usize) -> String {
+    table_line(columns, "├", "┼", "┤")
+}
+
+fn table_row(title: &str, cells: &[String]) -> String {
+    let cells: String = cells.iter().map(|cell| format!("    {cell:>14}  │")).collect();
+
+    format!("│  {title:<22}  │{cells}")
 }
@@ -0,0 +1,32 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use rand::prelude::*;
+use sonicprobe_core::{TruePeakMeter, frequency::Frequency};
+use std::hint::black_box;
+
+// Interpolation filter of the true-peak meter, every source sample runs one
+// 36 tap polyphase filter per output phase
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut rng = rand::rng();
+
+    let mut data: Vec<f64> = vec![0.0f64; 441_000]; // 10s @ 44.1k
+
+    // Fill the array with random values
+    for value in &mut data {
+        *value = rng.random::<f64>() * 2.0 - 1.0;
+    }
+
+    for rate in [Frequency::CD_QUALITY, Frequency::PRO_AUDIO, Frequency::DVD_AUDIO] {
+        c.bench_function(&format!("true peak {} Hz", rate.to_hz()), |b| {
+            b.iter(|| {
+                let mut meter = TruePeakMeter::new(rate);
+                for &sample in &data {
+                    black_box(meter.push_sample(black_box(sample)));
+                }
+                meter.value()
+            });
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
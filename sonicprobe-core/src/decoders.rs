@@ -0,0 +1,194 @@
+pub mod aiff;
+pub mod flac;
+pub mod lossy;
+#[cfg(feature = "opus")]
+pub mod opus;
+pub mod raw;
+pub mod wav;
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Seek},
+    path::Path,
+};
+
+use crate::{
+    decoders::{aiff::AiffDecoder, flac::FlacDecoder, lossy::LossyDecoder, wav::WavReader},
+    model::{
+        Samples,
+        channel_layout::{ChannelLabel, default_layout},
+        metadata::Metadata,
+        sonicprobe_error::SonicProbeError,
+    },
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SampleFormat {
+    Integer,
+    Float,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StreamInfo {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u32,
+    pub sample_format: SampleFormat,
+    pub samples_per_channel: Option<u64>,
+}
+
+pub trait AudioDecoder {
+    fn stream_info(&self) -> StreamInfo;
+
+    fn channel_layout(&self) -> Vec<ChannelLabel> {
+        default_layout(self.stream_info().channels)
+    }
+
+    fn metadata(&self) -> Metadata {
+        Metadata::default()
+    }
+
+    /// Interleaved samples scaled so that integer full scale maps to ±1.0,
+    /// `None` once the stream is exhausted
+    ///
+    /// # Errors
+    ///
+    /// Fails when the input cannot be read or holds a malformed block.
+    fn next_block(&mut self) -> Result<Option<&[Samples]>, SonicProbeError>;
+}
+
+/// # Errors
+///
+/// Fails when the file cannot be opened, or for the same reasons as [`decoder_from_reader`].
+pub fn open_decoder<P: AsRef<Path>>(path: P) -> Result<Box<dyn AudioDecoder>, SonicProbeError> {
+    let file = File::open(path)?;
+    decoder_from_reader(BufReader::new(file))
+}
+
+/// # Errors
+///
+/// Fails when the input is too short to detect its format, matches no supported format, or
+/// when the decoder for its format rejects the header.
+pub fn decoder_from_reader<R: BufRead + Seek + Send + Sync + 'static>(
+    mut reader: R,
+) -> Result<Box<dyn AudioDecoder>, SonicProbeError> {
+    let magic_bytes = peek_magic_bytes(&mut reader)?;
+
+    match &magic_bytes {
+        [b'f', b'L', b'a', b'C', ..] => Ok(Box::new(FlacDecoder::new(reader)?)),
+        [b'R', b'I', b'F', b'F', ..] | [b'R', b'F', b'6', b'4', ..] | [b'B', b'W', b'6', b'4', ..] => {
+            Ok(Box::new(WavReader::new(reader)?))
+        }
+        [b'F', b'O', b'R', b'M', ..] => Ok(Box::new(AiffDecoder::new(reader)?)),
+        [b'I', b'D', b'3', ..] | [b'O', b'g', b'g', b'S', ..] | [_, _, _, _, b'f', b't', b'y', b'p'] => {
+            Ok(Box::new(LossyDecoder::new(reader)?))
+        }
+        [0xFF, second, ..] if second & 0xE0 == 0xE0 => Ok(Box::new(LossyDecoder::new(reader)?)),
+        _ => Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "unrecognized audio format".to_owned(),
+        }),
+    }
+}
+
+/// Non-seekable input such as a pipe, only formats that can be decoded front to back are accepted
+///
+/// # Errors
+///
+/// Fails when the input is too short to detect its format, is neither FLAC nor WAV, or when the
+/// decoder rejects the header.
+pub fn decoder_from_stream<R: BufRead + 'static>(mut reader: R) -> Result<Box<dyn AudioDecoder>, SonicProbeError> {
+    let magic_bytes = peek_magic_bytes(&mut reader)?;
+
+    match &magic_bytes {
+        [b'f', b'L', b'a', b'C', ..] => Ok(Box::new(FlacDecoder::new(reader)?)),
+        [b'R', b'I', b'F', b'F', ..] | [b'R', b'F', b'6', b'4', ..] | [b'B', b'W', b'6', b'4', ..] => {
+            Ok(Box::new(WavReader::new(reader)?))
+        }
+        _ => Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "only FLAC and WAV can be read from a stream".to_owned(),
+        }),
+    }
+}
+
+fn peek_magic_bytes<R: BufRead>(reader: &mut R) -> Result<[u8; 8], SonicProbeError> {
+    let buffer = reader.fill_buf()?;
+
+    if buffer.len() < 4 {
+        return Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "stream is too short to detect its format".to_owned(),
+        });
+    }
+
+    let mut magic_bytes = [0u8; 8];
+    let length = buffer.len().min(magic_bytes.len());
+    magic_bytes[..length].copy_from_slice(&buffer[..length]);
+
+    Ok(magic_bytes)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::{self, Cursor, Read};
+
+    use super::*;
+
+    #[test]
+    fn detect_wav_by_magic_bytes() {
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&36u32.to_le_bytes());
+        file.extend_from_slice(b"WAVEfmt ");
+        file.extend_from_slice(&16u32.to_le_bytes());
+        file.extend_from_slice(&1u16.to_le_bytes());
+        file.extend_from_slice(&2u16.to_le_bytes());
+        file.extend_from_slice(&44100u32.to_le_bytes());
+        file.extend_from_slice(&176_400u32.to_le_bytes());
+        file.extend_from_slice(&4u16.to_le_bytes());
+        file.extend_from_slice(&16u16.to_le_bytes());
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&0u32.to_le_bytes());
+
+        let decoder = decoder_from_reader(Cursor::new(file)).unwrap();
+
+        assert_eq!(decoder.stream_info().sample_rate, 44100);
+    }
+
+    #[test]
+    fn reject_unknown_magic_bytes() {
+        let result = decoder_from_reader(Cursor::new(b"MThd\0\0\0\x06".to_vec()));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn detect_lossy_by_magic_bytes() {
+        let result = decoder_from_reader(Cursor::new(b"OggS\0\0\0\0".to_vec()));
+
+        assert!(result.is_err_and(|error| error.message.starts_with("lossy decoding error")));
+    }
+
+    #[test]
+    fn detect_flac_from_stream() {
+        let stream = crate::decoders::flac::test_utils::encode_flac(&[0; 200], 2, 48000, 16);
+        let decoder = decoder_from_stream(io::BufReader::new(io::Cursor::new(stream).take(u64::MAX))).unwrap();
+
+        assert_eq!(decoder.stream_info().sample_rate, 48000);
+    }
+
+    #[test]
+    fn reject_lossy_from_stream() {
+        let result = decoder_from_stream(Cursor::new(b"OggS\0\0\0\0".to_vec()));
+
+        assert!(result.is_err_and(|error| error.message.starts_with("only FLAC and WAV")));
+    }
+
+    #[test]
+    fn reject_short_stream() {
+        let result = decoder_from_reader(Cursor::new(b"fL".to_vec()));
+
+        assert!(result.is_err());
+    }
+}
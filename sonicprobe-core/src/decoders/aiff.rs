@@ -0,0 +1,480 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::{
+    decoders::{AudioDecoder, SampleFormat, StreamInfo},
+    model::{
+        Samples,
+        bit_depth::BitDepth,
+        metadata::{Marker, Metadata},
+        sonicprobe_error::SonicProbeError,
+    },
+};
+
+const FRAMES_PER_BLOCK: usize = 4096;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Encoding {
+    BigEndianInteger,
+    LittleEndianInteger,
+    BigEndianFloat,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct CommonChunk {
+    channels: u16,
+    frames: u32,
+    bits_per_sample: u16,
+    sample_rate: u32,
+    encoding: Encoding,
+}
+
+pub struct AiffDecoder<R: Read + Seek> {
+    reader: R,
+    common: CommonChunk,
+    metadata: Metadata,
+    remaining_samples: u64,
+    full_scale: f64,
+    buffer: Vec<Samples>,
+}
+
+impl<R: Read + Seek> AiffDecoder<R> {
+    /// # Errors
+    ///
+    /// Fails when the input cannot be read, is not an AIFF or AIFF-C file, holds a malformed or
+    /// unsupported `COMM` chunk, or has no `COMM` or `SSND` chunk.
+    pub fn new(mut reader: R) -> Result<Self, SonicProbeError> {
+        let form_id = read_id(&mut reader)?;
+        let form_size = read_u32(&mut reader)?;
+        let form_type = read_id(&mut reader)?;
+
+        if &form_id != b"FORM" {
+            return Err(format_error("missing FORM header"));
+        }
+
+        let is_aifc = match &form_type {
+            b"AIFF" => false,
+            b"AIFC" => true,
+            _ => return Err(format_error("FORM type is neither AIFF nor AIFC")),
+        };
+
+        let form_end = u64::from(form_size) + 8;
+        let mut common: Option<CommonChunk> = None;
+        let mut sound_data_start: Option<u64> = None;
+        let mut metadata = Metadata::default();
+
+        while reader.stream_position()? + 8 <= form_end {
+            let chunk_id = read_id(&mut reader)?;
+            let chunk_size = read_u32(&mut reader)?;
+            let chunk_start = reader.stream_position()?;
+
+            match &chunk_id {
+                b"COMM" => common = Some(read_common_chunk(&mut reader, is_aifc)?),
+                b"SSND" => {
+                    let offset = read_u32(&mut reader)?;
+                    let _block_size = read_u32(&mut reader)?;
+                    sound_data_start = Some(chunk_start + 8 + u64::from(offset));
+                }
+                b"NAME" => metadata.add_tag("Title", read_text(&mut reader, chunk_size)?),
+                b"AUTH" => metadata.add_tag("Author", read_text(&mut reader, chunk_size)?),
+                b"(c) " => metadata.add_tag("Copyright", read_text(&mut reader, chunk_size)?),
+                b"ANNO" => metadata.add_tag("Annotation", read_text(&mut reader, chunk_size)?),
+                b"MARK" => metadata.markers = read_markers(&mut reader)?,
+                _ => {}
+            }
+
+            let next_chunk = chunk_start + u64::from(chunk_size) + u64::from(chunk_size % 2);
+            reader.seek(SeekFrom::Start(next_chunk))?;
+        }
+
+        let Some(common) = common else {
+            return Err(format_error("missing COMM chunk"));
+        };
+
+        let Some(sound_data_start) = sound_data_start else {
+            return Err(format_error("missing SSND chunk"));
+        };
+
+        reader.seek(SeekFrom::Start(sound_data_start))?;
+
+        let full_scale = match common.encoding {
+            Encoding::BigEndianFloat => 1.0,
+            _ => BitDepth::new(u32::from(common.bits_per_sample))?.full_scale(),
+        };
+
+        Ok(Self {
+            reader,
+            common,
+            metadata,
+            remaining_samples: u64::from(common.frames) * u64::from(common.channels),
+            full_scale,
+            buffer: Vec::new(),
+        })
+    }
+
+    fn read_sample(&mut self) -> Result<f64, SonicProbeError> {
+        let container_bytes = usize::from(self.common.bits_per_sample.div_ceil(8));
+        let mut buffer = [0u8; 8];
+        self.reader.read_exact(&mut buffer[..container_bytes])?;
+        self.remaining_samples -= 1;
+
+        match self.common.encoding {
+            Encoding::BigEndianFloat if container_bytes == 4 => {
+                Ok(f64::from(f32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]])))
+            }
+            Encoding::BigEndianFloat => Ok(f64::from_be_bytes(buffer)),
+            Encoding::BigEndianInteger => {
+                let value = i32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+                Ok(f64::from(value >> (32 - self.common.bits_per_sample)))
+            }
+            Encoding::LittleEndianInteger => {
+                let mut value = 0i32;
+                for (index, byte) in buffer[..container_bytes].iter().enumerate() {
+                    value |= i32::from(*byte) << (8 * (4 - container_bytes + index));
+                }
+                Ok(f64::from(value >> (32 - self.common.bits_per_sample)))
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> AudioDecoder for AiffDecoder<R> {
+    fn stream_info(&self) -> StreamInfo {
+        StreamInfo {
+            channels: self.common.channels,
+            sample_rate: self.common.sample_rate,
+            bits_per_sample: u32::from(self.common.bits_per_sample),
+            sample_format: match self.common.encoding {
+                Encoding::BigEndianFloat => SampleFormat::Float,
+                _ => SampleFormat::Integer,
+            },
+            samples_per_channel: Some(u64::from(self.common.frames)),
+        }
+    }
+
+    fn metadata(&self) -> Metadata {
+        self.metadata.clone()
+    }
+
+    fn next_block(&mut self) -> Result<Option<&[Samples]>, SonicProbeError> {
+        if self.remaining_samples == 0 {
+            return Ok(None);
+        }
+
+        let full_scale = self.full_scale;
+        let block_size = FRAMES_PER_BLOCK * usize::from(self.common.channels);
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+
+        while buffer.len() < block_size && self.remaining_samples > 0 {
+            buffer.push(self.read_sample()? / full_scale);
+        }
+
+        self.buffer = buffer;
+
+        Ok(Some(&self.buffer))
+    }
+}
+
+fn read_common_chunk<R: Read>(reader: &mut R, is_aifc: bool) -> Result<CommonChunk, SonicProbeError> {
+    let channels = read_u16(reader)?;
+    let frames = read_u32(reader)?;
+    let bits_per_sample = read_u16(reader)?;
+    let sample_rate = read_extended(reader)?;
+
+    let compression_type = if is_aifc { read_id(reader)? } else { *b"NONE" };
+
+    let encoding = match &compression_type {
+        b"NONE" | b"twos" | b"in24" | b"in32" => Encoding::BigEndianInteger,
+        b"sowt" => Encoding::LittleEndianInteger,
+        b"fl32" | b"FL32" | b"fl64" | b"FL64" => Encoding::BigEndianFloat,
+        _ => {
+            return Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: format!(
+                    "unsupported AIFF-C compression type {}",
+                    String::from_utf8_lossy(&compression_type)
+                ),
+            })
+        }
+    };
+
+    let bits_per_sample = match &compression_type {
+        b"fl32" | b"FL32" | b"in32" => 32,
+        b"fl64" | b"FL64" => 64,
+        b"in24" => 24,
+        _ => bits_per_sample,
+    };
+
+    let max_bits = if encoding == Encoding::BigEndianFloat { 64 } else { 32 };
+
+    if channels == 0 || bits_per_sample == 0 || bits_per_sample > max_bits {
+        return Err(format_error("invalid COMM chunk"));
+    }
+
+    Ok(CommonChunk {
+        channels,
+        frames,
+        bits_per_sample,
+        sample_rate,
+        encoding,
+    })
+}
+
+fn read_markers<R: Read>(reader: &mut R) -> Result<Vec<Marker>, SonicProbeError> {
+    let count = read_u16(reader)?;
+    let mut markers = Vec::with_capacity(usize::from(count));
+
+    for _ in 0..count {
+        let id = read_u16(reader)?;
+        let position = read_u32(reader)?;
+        let name = read_pascal_string(reader)?;
+
+        markers.push(Marker {
+            id,
+            position: u64::from(position),
+            name,
+        });
+    }
+
+    Ok(markers)
+}
+
+fn read_pascal_string<R: Read>(reader: &mut R) -> Result<String, SonicProbeError> {
+    let mut length = [0u8; 1];
+    reader.read_exact(&mut length)?;
+
+    let padded_length = usize::from(length[0]) + usize::from(length[0] % 2 == 0);
+    let mut text = vec![0u8; padded_length];
+    reader.read_exact(&mut text)?;
+    text.truncate(usize::from(length[0]));
+
+    Ok(String::from_utf8_lossy(&text).into_owned())
+}
+
+fn read_text<R: Read>(reader: &mut R, chunk_size: u32) -> Result<String, SonicProbeError> {
+    let mut text = vec![0u8; usize::try_from(chunk_size)?];
+    reader.read_exact(&mut text)?;
+
+    Ok(String::from_utf8_lossy(&text).trim_end_matches('\0').to_owned())
+}
+
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn read_extended<R: Read>(reader: &mut R) -> Result<u32, SonicProbeError> {
+    let mut buffer = [0u8; 10];
+    reader.read_exact(&mut buffer)?;
+
+    let exponent = i32::from(u16::from_be_bytes([buffer[0], buffer[1]]) & 0x7FFF);
+    let mantissa = u64::from_be_bytes([
+        buffer[2], buffer[3], buffer[4], buffer[5], buffer[6], buffer[7], buffer[8], buffer[9],
+    ]);
+
+    if buffer[0] & 0x80 != 0 || exponent == 0x7FFF {
+        return Err(format_error("invalid sample rate"));
+    }
+
+    let shift = exponent - 16383 - 63;
+    let value = if shift >= 0 {
+        mantissa.checked_shl(shift.unsigned_abs()).unwrap_or(0)
+    } else {
+        mantissa.checked_shr(shift.unsigned_abs()).unwrap_or(0)
+    };
+
+    u32::try_from(value).map_err(|_| format_error("sample rate out of range"))
+}
+
+fn read_id<R: Read>(reader: &mut R) -> Result<[u8; 4], SonicProbeError> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16, SonicProbeError> {
+    let mut buffer = [0u8; 2];
+    reader.read_exact(&mut buffer)?;
+    Ok(u16::from_be_bytes(buffer))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, SonicProbeError> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_be_bytes(buffer))
+}
+
+fn format_error(message: &str) -> SonicProbeError {
+    SonicProbeError {
+        location: format!("{}:{}", file!(), line!()),
+        message: format!("invalid AIFF stream: {message}"),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn extended(sample_rate: u32) -> [u8; 10] {
+        let exponent = 16383 + sample_rate.ilog2();
+        let mantissa = u64::from(sample_rate) << (63 - sample_rate.ilog2());
+        let mut output = [0u8; 10];
+        output[0..2].copy_from_slice(&u16::try_from(exponent).unwrap().to_be_bytes());
+        output[2..10].copy_from_slice(&mantissa.to_be_bytes());
+        output
+    }
+
+    fn chunk(id: [u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut output = id.to_vec();
+        output.extend_from_slice(&u32::try_from(body.len()).unwrap().to_be_bytes());
+        output.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            output.push(0);
+        }
+        output
+    }
+
+    fn common(channels: u16, frames: u32, bits: u16, sample_rate: u32, compression: Option<&[u8; 4]>) -> Vec<u8> {
+        let mut body = channels.to_be_bytes().to_vec();
+        body.extend_from_slice(&frames.to_be_bytes());
+        body.extend_from_slice(&bits.to_be_bytes());
+        body.extend_from_slice(&extended(sample_rate));
+        if let Some(compression) = compression {
+            body.extend_from_slice(compression);
+            body.extend_from_slice(&[0, 0]);
+        }
+        chunk(*b"COMM", &body)
+    }
+
+    fn sound_data(data: &[u8]) -> Vec<u8> {
+        let mut body = vec![0u8; 8];
+        body.extend_from_slice(data);
+        chunk(*b"SSND", &body)
+    }
+
+    fn form(form_type: [u8; 4], chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut output = b"FORM".to_vec();
+        output.extend_from_slice(&u32::try_from(body.len() + 4).unwrap().to_be_bytes());
+        output.extend_from_slice(&form_type);
+        output.extend_from_slice(&body);
+        output
+    }
+
+    fn decode_all(bytes: Vec<u8>) -> (StreamInfo, Metadata, Vec<f64>) {
+        let mut decoder = AiffDecoder::new(Cursor::new(bytes)).unwrap();
+        let mut samples = Vec::new();
+        while let Some(block) = decoder.next_block().unwrap() {
+            samples.extend_from_slice(block);
+        }
+        (decoder.stream_info(), decoder.metadata(), samples)
+    }
+
+    #[test]
+    fn sample_rate_extended_decoding() {
+        for rate in [8000u32, 44100, 48000, 96000, 192_000] {
+            let result = read_extended(&mut Cursor::new(extended(rate))).unwrap();
+            assert_eq!(result, rate);
+        }
+    }
+
+    #[test]
+    fn big_endian_16_bit() {
+        let data: Vec<u8> = [32767i16, -32767, 0, 16384]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        let file = form(*b"AIFF", &[common(2, 2, 16, 44100, None), sound_data(&data)]);
+        let (info, _, samples) = decode_all(file);
+
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.samples_per_channel, Some(2));
+        assert_eq!(samples, vec![1.0, -1.0, 0.0, 16384.0 / 32767.0]);
+    }
+
+    #[test]
+    fn big_endian_24_bit() {
+        let data = [0x7F, 0xFF, 0xFF, 0x80, 0x00, 0x01];
+        let file = form(*b"AIFF", &[common(2, 1, 24, 96000, None), sound_data(&data)]);
+        let (_, _, samples) = decode_all(file);
+
+        assert_eq!(samples, vec![1.0, -1.0]);
+    }
+
+    #[test]
+    fn aifc_sowt_little_endian() {
+        let data: Vec<u8> = [32767i16, -16384]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let file = form(*b"AIFC", &[common(2, 1, 16, 48000, Some(b"sowt")), sound_data(&data)]);
+        let (info, _, samples) = decode_all(file);
+
+        assert_eq!(info.sample_format, SampleFormat::Integer);
+        assert_eq!(samples, vec![1.0, -16384.0 / 32767.0]);
+    }
+
+    #[test]
+    fn aifc_fl32_float() {
+        let data: Vec<u8> = [0.5f32, -1.5]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        let file = form(*b"AIFC", &[common(2, 1, 32, 48000, Some(b"fl32")), sound_data(&data)]);
+        let (info, _, samples) = decode_all(file);
+
+        assert_eq!(info.sample_format, SampleFormat::Float);
+        assert_eq!(samples, vec![0.5, -1.5]);
+    }
+
+    #[test]
+    fn metadata_chunks_after_sound_data() {
+        let data = [0u8; 4];
+        let mut markers = 2u16.to_be_bytes().to_vec();
+        markers.extend_from_slice(&1u16.to_be_bytes());
+        markers.extend_from_slice(&0u32.to_be_bytes());
+        markers.extend_from_slice(&[5, b'I', b'n', b't', b'r', b'o']);
+        markers.extend_from_slice(&2u16.to_be_bytes());
+        markers.extend_from_slice(&44100u32.to_be_bytes());
+        markers.extend_from_slice(&[4, b'D', b'r', b'o', b'p', 0]);
+
+        let file = form(
+            *b"AIFF",
+            &[
+                common(2, 1, 16, 44100, None),
+                sound_data(&data),
+                chunk(*b"NAME", b"Master v3"),
+                chunk(*b"AUTH", b"Engineer"),
+                chunk(*b"MARK", &markers),
+            ],
+        );
+        let (_, metadata, samples) = decode_all(file);
+
+        assert_eq!(samples.len(), 2);
+        assert_eq!(metadata.tags[0].key, "Title");
+        assert_eq!(metadata.tags[0].value, "Master v3");
+        assert_eq!(metadata.tags[1].key, "Author");
+        assert_eq!(metadata.tags[1].value, "Engineer");
+        assert_eq!(metadata.markers.len(), 2);
+        assert_eq!(metadata.markers[0].name, "Intro");
+        assert_eq!(metadata.markers[1].name, "Drop");
+        assert_eq!(metadata.markers[1].position, 44100);
+    }
+
+    #[test]
+    fn unsupported_compression() {
+        let file = form(*b"AIFC", &[common(2, 1, 16, 48000, Some(b"ima4")), sound_data(&[0; 4])]);
+
+        assert!(AiffDecoder::new(Cursor::new(file)).is_err());
+    }
+
+    #[test]
+    fn missing_sound_data() {
+        let file = form(*b"AIFF", &[common(2, 1, 16, 48000, None)]);
+
+        assert!(AiffDecoder::new(Cursor::new(file)).is_err());
+    }
+}
@@ -0,0 +1,286 @@
+use std::{io::Read, mem};
+
+use claxon::FlacReader;
+
+use crate::{
+    decoders::{AudioDecoder, SampleFormat, StreamInfo},
+    model::{
+        Samples,
+        bit_depth::BitDepth,
+        channel_layout::{ChannelLabel, flac_layout},
+        sonicprobe_error::SonicProbeError,
+    },
+};
+
+pub struct FlacDecoder<R: Read> {
+    reader: FlacReader<R>,
+    info: StreamInfo,
+    full_scale: f64,
+    raw_buffer: Vec<i32>,
+    buffer: Vec<Samples>,
+}
+
+impl<R: Read> FlacDecoder<R> {
+    /// # Errors
+    ///
+    /// Fails when the input is not a FLAC stream, its header is malformed, or it declares an
+    /// unsupported channel count or bit depth.
+    pub fn new(reader: R) -> Result<Self, SonicProbeError> {
+        let reader = FlacReader::new(reader).map_err(|error| from_claxon_error(&error))?;
+        let streaminfo = reader.streaminfo();
+
+        let info = StreamInfo {
+            channels: u16::try_from(streaminfo.channels)?,
+            sample_rate: streaminfo.sample_rate,
+            bits_per_sample: streaminfo.bits_per_sample,
+            sample_format: SampleFormat::Integer,
+            samples_per_channel: streaminfo.samples,
+        };
+
+        Ok(Self {
+            reader,
+            info,
+            full_scale: BitDepth::new(streaminfo.bits_per_sample)?.full_scale(),
+            raw_buffer: Vec::new(),
+            buffer: Vec::new(),
+        })
+    }
+}
+
+impl<R: Read> AudioDecoder for FlacDecoder<R> {
+    fn stream_info(&self) -> StreamInfo {
+        self.info
+    }
+
+    fn channel_layout(&self) -> Vec<ChannelLabel> {
+        flac_layout(self.info.channels)
+    }
+
+    fn next_block(&mut self) -> Result<Option<&[Samples]>, SonicProbeError> {
+        let raw_buffer = mem::take(&mut self.raw_buffer);
+
+        let Some(block) = self.reader.blocks().read_next_or_eof(raw_buffer).map_err(|error| from_claxon_error(&error))? else {
+            return Ok(None);
+        };
+
+        self.buffer.clear();
+        self.buffer.reserve(block.len() as usize);
+
+        for index in 0..block.duration() {
+            for channel in 0..block.channels() {
+                self.buffer.push(f64::from(block.sample(channel, index)) / self.full_scale);
+            }
+        }
+
+        self.raw_buffer = block.into_buffer();
+
+        Ok(Some(&self.buffer))
+    }
+}
+
+fn from_claxon_error(error: &claxon::Error) -> SonicProbeError {
+    SonicProbeError {
+        location: format!("{}:{}", file!(), line!()),
+        message: format!("FLAC decoding error: {error}"),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::io::Cursor;
+
+    use crate::model::{audio_file::AudioFile, builders::audio_file_builder::audio_file_form_stream};
+
+    fn crc8(data: &[u8]) -> u8 {
+        data.iter().fold(0u8, |crc, &byte| {
+            (0..8).fold(crc ^ byte, |crc, _| if crc & 0x80 == 0 { crc << 1 } else { (crc << 1) ^ 0x07 })
+        })
+    }
+
+    fn crc16(data: &[u8]) -> u16 {
+        data.iter().fold(0u16, |crc, &byte| {
+            (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+                if crc & 0x8000 == 0 { crc << 1 } else { (crc << 1) ^ 0x8005 }
+            })
+        })
+    }
+
+    const fn sample_size_code(bits_per_sample: usize) -> u64 {
+        match bits_per_sample {
+            8 => 0b001,
+            12 => 0b010,
+            16 => 0b100,
+            20 => 0b101,
+            24 => 0b110,
+            _ => 0b000,
+        }
+    }
+
+    // MSB first, as FLAC, MPEG and AAC headers are laid out
+    pub struct BitWriter {
+        pub bytes: Vec<u8>,
+        pub bit_count: usize,
+    }
+
+    impl BitWriter {
+        pub fn write(&mut self, value: u64, bits: usize) {
+            for shift in (0..bits).rev() {
+                if self.bit_count.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                if (value >> shift) & 1 == 1 {
+                    if let Some(last) = self.bytes.last_mut() {
+                        *last |= 0x80 >> (self.bit_count % 8);
+                    }
+                }
+                self.bit_count += 1;
+            }
+        }
+    }
+
+    // Minimal FLAC stream made of VERBATIM subframes, interleaved input
+    #[allow(clippy::cast_sign_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn encode_flac(samples: &[i32], channels: usize, sample_rate: u32, bits_per_sample: usize) -> Vec<u8> {
+        const BLOCK_SIZE: usize = 4096;
+        let frames = samples.len() / channels;
+
+        let mut stream = b"fLaC".to_vec();
+        let mut streaminfo = BitWriter { bytes: Vec::new(), bit_count: 0 };
+        streaminfo.write(BLOCK_SIZE as u64, 16);
+        streaminfo.write(BLOCK_SIZE as u64, 16);
+        streaminfo.write(0, 24);
+        streaminfo.write(0, 24);
+        streaminfo.write(u64::from(sample_rate), 20);
+        streaminfo.write(channels as u64 - 1, 3);
+        streaminfo.write(bits_per_sample as u64 - 1, 5);
+        streaminfo.write(frames as u64, 36);
+        streaminfo.write(0, 64);
+        streaminfo.write(0, 64);
+
+        stream.extend_from_slice(&[0x80, 0, 0, 34]);
+        stream.extend_from_slice(&streaminfo.bytes);
+
+        for (frame_number, block) in samples.chunks(BLOCK_SIZE * channels).enumerate() {
+            let block_size = block.len() / channels;
+            let mut frame = BitWriter { bytes: Vec::new(), bit_count: 0 };
+            frame.write(0b1111_1111_1111_1000, 16);
+            frame.write(0b0111, 4);
+            frame.write(0b0000, 4);
+            frame.write(channels as u64 - 1, 4);
+            frame.write(sample_size_code(bits_per_sample), 3);
+            frame.write(0, 1);
+            if frame_number < 0x80 {
+                frame.write(frame_number as u64, 8);
+            } else {
+                frame.write(0xC0 | (frame_number as u64 >> 6), 8);
+                frame.write(0x80 | (frame_number as u64 & 0x3F), 8);
+            }
+            frame.write(block_size as u64 - 1, 16);
+            let header_crc = crc8(&frame.bytes);
+            frame.write(u64::from(header_crc), 8);
+
+            for channel in 0..channels {
+                frame.write(0b0000_0010, 8);
+                for index in 0..block_size {
+                    let value = block[index * channels + channel];
+                    frame.write(u64::from(value as u32) & ((1 << bits_per_sample) - 1), bits_per_sample);
+                }
+            }
+
+            let padding = (8 - frame.bit_count % 8) % 8;
+            frame.write(0, padding);
+            let frame_crc = crc16(&frame.bytes);
+            frame.write(u64::from(frame_crc), 16);
+
+            stream.extend_from_slice(&frame.bytes);
+        }
+
+        stream
+    }
+
+    // Full analysis of interleaved samples, through the FLAC decoder like a file on disk
+    #[allow(clippy::unwrap_used)]
+    pub fn analyse_samples(samples: &[i32], channels: usize, sample_rate: u32, bits_per_sample: usize) -> AudioFile {
+        let mut decoder = super::FlacDecoder::new(Cursor::new(encode_flac(samples, channels, sample_rate, bits_per_sample))).unwrap();
+        audio_file_form_stream(&mut decoder).unwrap()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::decoders::flac::test_utils::encode_flac;
+
+    #[test]
+    fn stream_info() {
+        let decoder = FlacDecoder::new(Cursor::new(encode_flac(&[0; 200], 2, 48000, 16))).unwrap();
+        let info = decoder.stream_info();
+
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.sample_format, SampleFormat::Integer);
+        assert_eq!(info.samples_per_channel, Some(100));
+    }
+
+    #[test]
+    fn interleaved_normalized_samples() {
+        let samples = [32767, -32767, 0, 16384];
+        let mut decoder = FlacDecoder::new(Cursor::new(encode_flac(&samples, 2, 44100, 16))).unwrap();
+        let block = decoder.next_block().unwrap().unwrap().to_vec();
+
+        assert_eq!(block, vec![1.0, -1.0, 0.0, 16384.0 / 32767.0]);
+        assert!(decoder.next_block().unwrap().is_none());
+    }
+
+    #[test]
+    fn multiple_blocks() {
+        let samples: Vec<i32> = (0..20000).map(|value| value % 1000 - 500).collect();
+        let mut decoder = FlacDecoder::new(Cursor::new(encode_flac(&samples, 2, 44100, 16))).unwrap();
+        let mut collected = Vec::new();
+
+        while let Some(block) = decoder.next_block().unwrap() {
+            collected.extend_from_slice(block);
+        }
+
+        let expected: Vec<f64> = samples.iter().map(|value| f64::from(*value) / 32767.0).collect();
+        assert_eq!(collected, expected);
+    }
+
+    #[test]
+    fn twelve_bit_stream() {
+        let samples = [2047, -2047, 1024, 0];
+        let mut decoder = FlacDecoder::new(Cursor::new(encode_flac(&samples, 2, 44100, 12))).unwrap();
+        let block = decoder.next_block().unwrap().unwrap().to_vec();
+
+        assert_eq!(decoder.stream_info().bits_per_sample, 12);
+        assert_eq!(block, vec![1.0, -1.0, 1024.0 / 2047.0, 0.0]);
+    }
+
+    #[test]
+    fn unknown_total_samples() {
+        let samples: Vec<i32> = (0..10000).map(|value| value % 100).collect();
+        let mut stream = encode_flac(&samples, 2, 44100, 16);
+        stream[21] &= 0xF0;
+        stream[22..26].fill(0);
+
+        let mut decoder = FlacDecoder::new(Cursor::new(stream)).unwrap();
+        let mut frames = 0;
+        while let Some(block) = decoder.next_block().unwrap() {
+            frames += block.len() / 2;
+        }
+
+        assert_eq!(decoder.stream_info().samples_per_channel, None);
+        assert_eq!(frames, 5000);
+    }
+
+    #[test]
+    fn invalid_stream() {
+        assert!(FlacDecoder::new(Cursor::new(b"fLaC\0\0".to_vec())).is_err());
+    }
+}
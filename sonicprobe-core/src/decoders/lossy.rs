@@ -0,0 +1,614 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use symphonia::core::{
+    audio::{Channels, SampleBuffer},
+    codecs::{CODEC_TYPE_AAC, CODEC_TYPE_NULL, CODEC_TYPE_OPUS, CodecRegistry, Decoder, DecoderOptions},
+    errors::Error as SymphoniaError,
+    formats::{FormatOptions, FormatReader},
+    io::{MediaSource, MediaSourceStream, MediaSourceStreamOptions},
+    meta::{MetadataOptions, Value},
+    probe::Hint,
+};
+
+#[cfg(feature = "opus")]
+use crate::decoders::opus::OpusDecoder;
+use crate::{
+    decoders::{AudioDecoder, SampleFormat, StreamInfo},
+    model::{
+        Samples,
+        channel_layout::{ChannelLabel, default_layout, layout_from_mask},
+        sonicprobe_error::SonicProbeError,
+    },
+};
+
+// MP3 (LAME tag) and Vorbis delays are trimmed by the demuxers in gapless mode,
+// AAC in MP4 relies on the iTunSMPB tag and Opus on the OpusHead pre-skip, both
+// applied here
+pub struct LossyDecoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    info: StreamInfo,
+    layout: Vec<ChannelLabel>,
+    trim: Trim,
+    buffer: Vec<Samples>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Trim {
+    skip: u64,
+    remaining: Option<u64>,
+}
+
+// The length lets the Ogg demuxer reach the last page, whose granule position bounds the padding
+struct SeekableSource<R> {
+    reader: R,
+    length: u64,
+}
+
+impl<R: Read> Read for SeekableSource<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for SeekableSource<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+impl<R: Read + Seek + Send + Sync> MediaSource for SeekableSource<R> {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.length)
+    }
+}
+
+impl LossyDecoder {
+    /// # Errors
+    ///
+    /// Fails when the input cannot be read, holds no audio track the probe recognises, uses a codec
+    /// without a decoder in this build, or leaves its channel layout or sample rate unknown.
+    pub fn new<R: Read + Seek + Send + Sync + 'static>(mut reader: R) -> Result<Self, SonicProbeError> {
+        let start = reader.stream_position()?;
+        let length = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+
+        let source = MediaSourceStream::new(Box::new(SeekableSource { reader, length }), MediaSourceStreamOptions::default());
+        let format_options = FormatOptions { enable_gapless: true, ..Default::default() };
+
+        let mut probed = symphonia::default::get_probe()
+            .format(&Hint::new(), source, &format_options, &MetadataOptions::default())
+            .map_err(|error| from_symphonia_error(&error))?;
+
+        let track = probed
+            .format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: "no audio track found".to_owned(),
+            })?;
+        let params = track.codec_params.clone();
+        let track_id = track.id;
+
+        #[cfg(not(feature = "opus"))]
+        if params.codec == CODEC_TYPE_OPUS {
+            return Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: "Opus decoding needs a build with the opus feature".to_owned(),
+            });
+        }
+
+        let decoder = codecs()
+            .make(&params, &DecoderOptions::default())
+            .map_err(|error| from_symphonia_error(&error))?;
+
+        // The MP4 demuxer leaves the AAC channels in the AudioSpecificConfig
+        let channels =
+            params.channels.or_else(|| params.extra_data.as_deref().and_then(aac_channels)).ok_or_else(|| SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: "unknown channel layout".to_owned(),
+            })?;
+        let sample_rate = params.sample_rate.ok_or_else(|| SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "unknown sample rate".to_owned(),
+        })?;
+
+        let itunes_gapless = if params.codec == CODEC_TYPE_AAC {
+            probed
+                .format
+                .metadata()
+                .current()
+                .and_then(|revision| {
+                    revision.tags().iter().find(|tag| tag.key.ends_with("iTunSMPB")).map(|tag| tag.value.clone())
+                })
+                .and_then(|value| match value {
+                    Value::String(text) => parse_itunsmpb(&text),
+                    _ => None,
+                })
+        } else {
+            None
+        };
+
+        // The Ogg granule positions of Opus count the pre-skip, which only the decoder output holds
+        let opus_gapless = if params.codec == CODEC_TYPE_OPUS {
+            params.extra_data.as_deref().and_then(parse_opus_pre_skip).map(|skip| Trim {
+                skip,
+                remaining: params.n_frames.map(|frames| frames.saturating_sub(skip)),
+            })
+        } else {
+            None
+        };
+
+        let trim = itunes_gapless.or(opus_gapless).unwrap_or(Trim { skip: 0, remaining: None });
+
+        let info = StreamInfo {
+            channels: u16::try_from(channels.count())?,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+            samples_per_channel: trim.remaining.or(params.n_frames),
+        };
+
+        Ok(Self {
+            format: probed.format,
+            decoder,
+            track_id,
+            info,
+            layout: match info.channels {
+                1 | 2 => default_layout(info.channels),
+                _ => layout_from_mask(channels.bits(), info.channels),
+            },
+            trim,
+            buffer: Vec::new(),
+        })
+    }
+}
+
+impl AudioDecoder for LossyDecoder {
+    fn stream_info(&self) -> StreamInfo {
+        self.info
+    }
+
+    fn channel_layout(&self) -> Vec<ChannelLabel> {
+        self.layout.clone()
+    }
+
+    fn next_block(&mut self) -> Result<Option<&[Samples]>, SonicProbeError> {
+        let channels = usize::from(self.info.channels);
+
+        loop {
+            if self.trim.remaining == Some(0) {
+                return Ok(None);
+            }
+
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(error)) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(error) => return Err(from_symphonia_error(&error)),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(error) => return Err(from_symphonia_error(&error)),
+            };
+
+            let mut sample_buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+            sample_buffer.copy_interleaved_ref(decoded);
+
+            let frames = sample_buffer.samples().chunks_exact(channels);
+            let (skip, take) = self.trim.apply(frames.len() as u64);
+
+            self.buffer.clear();
+            for frame in frames.skip(usize::try_from(skip)?).take(usize::try_from(take)?) {
+                self.buffer.extend(frame.iter().map(|sample| f64::from(*sample)));
+            }
+
+            if !self.buffer.is_empty() {
+                return Ok(Some(&self.buffer));
+            }
+        }
+    }
+}
+
+impl Trim {
+    // Returns how many frames of a decoded block to drop and to keep
+    fn apply(&mut self, frames: u64) -> (u64, u64) {
+        let skip = self.skip.min(frames);
+        self.skip -= skip;
+
+        let mut take = frames - skip;
+        if let Some(remaining) = self.remaining.as_mut() {
+            take = take.min(*remaining);
+            *remaining -= take;
+        }
+
+        (skip, take)
+    }
+}
+
+// " 00000000 00000840 000001CA 00000000004A8B76 ..." holds the encoder delay,
+// the padding and the original length, in frames
+fn parse_itunsmpb(value: &str) -> Option<Trim> {
+    let mut fields = value.split_whitespace().skip(1).map(|field| u64::from_str_radix(field, 16));
+
+    let skip = fields.next()?.ok()?;
+    let _padding = fields.next()?.ok()?;
+    let length = fields.next()?.ok()?;
+
+    Some(Trim { skip, remaining: (length > 0).then_some(length) })
+}
+
+// AudioSpecificConfig: 5 bits of object type, 4 of sampling frequency index, then the
+// channel configuration, 1 for mono and 2 for stereo in the AAC-LC streams symphonia decodes
+fn aac_channels(config: &[u8]) -> Option<Channels> {
+    match (config.get(1)? >> 3) & 0x0F {
+        1 => Some(Channels::FRONT_LEFT),
+        2 => Some(Channels::FRONT_LEFT | Channels::FRONT_RIGHT),
+        _ => None,
+    }
+}
+
+// OpusHead: "OpusHead", version, channel count, then the pre-skip as a little-endian
+// u16, the frames at 48 kHz to drop before the first output sample
+fn parse_opus_pre_skip(head: &[u8]) -> Option<u64> {
+    if !head.starts_with(b"OpusHead") {
+        return None;
+    }
+
+    let bytes = head.get(10..12)?.try_into().ok()?;
+
+    Some(u64::from(u16::from_le_bytes(bytes)))
+}
+
+// Symphonia's own codecs, plus libopus when built with the opus feature
+fn codecs() -> CodecRegistry {
+    let mut registry = CodecRegistry::new();
+    symphonia::default::register_enabled_codecs(&mut registry);
+
+    #[cfg(feature = "opus")]
+    registry.register_all::<OpusDecoder>();
+
+    registry
+}
+
+fn from_symphonia_error(error: &SymphoniaError) -> SonicProbeError {
+    SonicProbeError {
+        location: format!("{}:{}", file!(), line!()),
+        message: format!("lossy decoding error: {error}"),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        decoders::flac::test_utils::BitWriter,
+        model::{audio_file::AudioFile, builders::audio_file_builder::audio_file_form_stream},
+    };
+
+    // MPEG-1 Layer III, 128 kbps, 44.1 kHz, mono, no CRC
+    const MP3_HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0xC0];
+    const MP3_FRAME_SIZE: usize = 417;
+    const MP3_FRAME_LENGTH: u64 = 1152;
+    const AAC_FRAME_LENGTH: u64 = 1024;
+
+    fn decode(bytes: Vec<u8>) -> (Option<u64>, AudioFile) {
+        let mut decoder = LossyDecoder::new(Cursor::new(bytes)).unwrap();
+        let samples_per_channel = decoder.stream_info().samples_per_channel;
+
+        (samples_per_channel, audio_file_form_stream(&mut decoder).unwrap())
+    }
+
+    // Each granule holds a single spectral line in the count1 region: three zero
+    // quadruples and (1, 0, 0, 0) from table B, the global gain sets the level
+    fn mp3_frame(global_gain: u64) -> Vec<u8> {
+        let mut frame = BitWriter { bytes: MP3_HEADER.to_vec(), bit_count: 32 };
+        frame.write(0, 18);
+        for _ in 0..2 {
+            frame.write(17, 12);
+            frame.write(0, 9);
+            frame.write(global_gain, 8);
+            frame.write(0, 28);
+            frame.write(1, 1);
+        }
+        for _ in 0..2 {
+            frame.write(0xFFF, 12);
+            frame.write(0b0111, 4);
+            frame.write(0, 1);
+        }
+        frame.bytes.resize(MP3_FRAME_SIZE, 0);
+        frame.bytes
+    }
+
+    // Info tag with the LAME extension as written by FFmpeg, which carries no CRC
+    fn mp3_info_frame(frames: u32, delay: u32, padding: u32) -> Vec<u8> {
+        let mut frame = MP3_HEADER.to_vec();
+        frame.resize(21, 0);
+        frame.extend_from_slice(b"Info");
+        frame.extend_from_slice(&1u32.to_be_bytes());
+        frame.extend_from_slice(&frames.to_be_bytes());
+        frame.extend_from_slice(b"Lavf58.76");
+        frame.resize(frame.len() + 12, 0);
+        frame.extend_from_slice(&(delay << 12 | padding).to_be_bytes()[1..]);
+        frame.resize(MP3_FRAME_SIZE, 0);
+        frame
+    }
+
+    // Mono AAC-LC raw data block whose first 40 bands are noise substituted,
+    // the 9 bit energy of the first band sets the level of all of them
+    fn aac_frame(noise_energy: u64) -> Vec<u8> {
+        let mut frame = BitWriter { bytes: Vec::new(), bit_count: 0 };
+        frame.write(0, 7);
+        frame.write(100, 8);
+        frame.write(0, 4);
+        frame.write(40, 6);
+        frame.write(0, 1);
+        frame.write(13, 4);
+        frame.write(31, 5);
+        frame.write(9, 5);
+        frame.write(noise_energy, 9);
+        frame.write(0, 39);
+        frame.write(0, 3);
+        frame.write(0b111, 3);
+        frame.bytes
+    }
+
+    fn atom(kind: [u8; 4], body: &[u8]) -> Vec<u8> {
+        [&(body.len() as u32 + 8).to_be_bytes(), kind.as_slice(), body].concat()
+    }
+
+    fn full_atom(kind: [u8; 4], body: &[u8]) -> Vec<u8> {
+        atom(kind, &[&[0; 4], body].concat())
+    }
+
+    // M4A with one mono 44.1 kHz AAC track in a single chunk and an iTunSMPB tag,
+    // the bindings are named after their atoms
+    #[allow(clippy::similar_names)]
+    fn m4a(frames: &[Vec<u8>], itunsmpb: &str) -> Vec<u8> {
+        let count = frames.len() as u32;
+        let duration = (count * AAC_FRAME_LENGTH as u32).to_be_bytes();
+
+        let audio_specific_config = [0x05, 2, 0x12, 0x08];
+        let decoder_config = [&[0x04, 17, 0x40, 0x15][..], &[0; 11], &audio_specific_config].concat();
+        let es_descriptor = [&[0x03, 25, 0, 1, 0][..], &decoder_config, &[0x06, 1, 2]].concat();
+        let sample_entry =
+            [&[0, 0, 0, 0, 0, 0, 0, 1][..], &[0; 8], &[0, 1, 0, 16, 0, 0, 0, 0], &(44100u32 << 16).to_be_bytes()].concat();
+        let stsd = [&1u32.to_be_bytes()[..], &atom(*b"mp4a", &[sample_entry, full_atom(*b"esds", &es_descriptor)].concat())].concat();
+        let stts = [1, count, AAC_FRAME_LENGTH as u32].map(u32::to_be_bytes).concat();
+        let stsc = [1, 1, count, 1].map(u32::to_be_bytes).concat();
+        let sizes: Vec<u8> = frames.iter().flat_map(|frame| (frame.len() as u32).to_be_bytes()).collect();
+        let stsz = [&0u32.to_be_bytes()[..], &count.to_be_bytes(), &sizes].concat();
+
+        let moov = |mdat_offset: u32| {
+            let stco = [1, mdat_offset].map(u32::to_be_bytes).concat();
+            let stbl = [
+                full_atom(*b"stsd", &stsd),
+                full_atom(*b"stts", &stts),
+                full_atom(*b"stsc", &stsc),
+                full_atom(*b"stsz", &stsz),
+                full_atom(*b"stco", &stco),
+            ]
+            .concat();
+            let mdhd = [&[0; 8][..], &44100u32.to_be_bytes(), &duration, &[0x55, 0xC4, 0, 0]].concat();
+            let hdlr = [&[0; 4][..], b"soun", &[0; 13]].concat();
+            let mdia = [full_atom(*b"mdhd", &mdhd), full_atom(*b"hdlr", &hdlr), atom(*b"minf", &atom(*b"stbl", &stbl))].concat();
+            let tkhd = [&[0; 8][..], &1u32.to_be_bytes(), &[0; 68]].concat();
+            let mvhd = [&[0; 8][..], &44100u32.to_be_bytes(), &duration, &[0; 80]].concat();
+
+            let data = [&[0, 0, 0, 1, 0, 0, 0, 0][..], itunsmpb.as_bytes()].concat();
+            let tag = [full_atom(*b"mean", b"com.apple.iTunes"), full_atom(*b"name", b"iTunSMPB"), atom(*b"data", &data)].concat();
+            let meta_hdlr = [&[0; 4][..], b"mdir", &[0; 13]].concat();
+            let meta = [full_atom(*b"hdlr", &meta_hdlr), atom(*b"ilst", &atom(*b"----", &tag))].concat();
+
+            let trak = [full_atom(*b"tkhd", &tkhd), atom(*b"mdia", &mdia)].concat();
+            atom(*b"moov", &[full_atom(*b"mvhd", &mvhd), atom(*b"trak", &trak), atom(*b"udta", &full_atom(*b"meta", &meta))].concat())
+        };
+
+        let ftyp = atom(*b"ftyp", b"M4A \0\0\0\0M4A mp42isom");
+        let mdat_offset = (ftyp.len() + moov(0).len() + 8) as u32;
+
+        [ftyp, moov(mdat_offset), atom(*b"mdat", &frames.concat())].concat()
+    }
+
+    #[test]
+    fn parse_itunes_gapless_info() {
+        let trim =
+            parse_itunsmpb(" 00000000 00000840 000001CA 00000000004A8B76 00000000 00000000 00000000 00000000").unwrap();
+
+        assert_eq!(trim, Trim { skip: 2112, remaining: Some(4_885_366) });
+    }
+
+    #[test]
+    fn parse_invalid_itunes_gapless_info() {
+        assert!(parse_itunsmpb("garbage").is_none());
+        assert!(parse_itunsmpb(" 00000000 zz 000001CA 00000000004A8B76").is_none());
+    }
+
+    #[test]
+    fn trim_delay_across_blocks() {
+        let mut trim = Trim { skip: 2112, remaining: None };
+
+        assert_eq!(trim.apply(1024), (1024, 0));
+        assert_eq!(trim.apply(1024), (1024, 0));
+        assert_eq!(trim.apply(1024), (64, 960));
+        assert_eq!(trim.apply(1024), (0, 1024));
+    }
+
+    #[test]
+    fn trim_padding_at_the_end() {
+        let mut trim = Trim { skip: 100, remaining: Some(1500) };
+
+        assert_eq!(trim.apply(1024), (100, 924));
+        assert_eq!(trim.apply(1024), (0, 576));
+        assert_eq!(trim.apply(1024), (0, 0));
+    }
+
+    #[test]
+    fn parse_opus_header_pre_skip() {
+        let head = [b"OpusHead".as_slice(), &[1, 2], &312u16.to_le_bytes(), &48000u32.to_le_bytes(), &[0, 0, 0]].concat();
+
+        assert_eq!(parse_opus_pre_skip(&head), Some(312));
+        assert!(parse_opus_pre_skip(b"OpusTags\x01\x02\x38\x01").is_none());
+        assert!(parse_opus_pre_skip(b"OpusHead\x01").is_none());
+    }
+
+    #[test]
+    fn mp3_delay_and_padding_from_the_lame_tag() {
+        // 20 frames around -15 dBFS, then 20 driven well past full scale
+        let mut bytes = mp3_info_frame(40, 576, 1000);
+        for global_gain in [200; 20].into_iter().chain([212; 20]) {
+            bytes.extend(mp3_frame(global_gain));
+        }
+
+        let (samples_per_channel, audio_file) = decode(bytes);
+        let expected = 40 * MP3_FRAME_LENGTH - 576 - 1000;
+
+        assert_eq!(samples_per_channel, Some(expected));
+        assert_eq!(audio_file.samples_per_channel as u64, expected);
+        assert_eq!(audio_file.duration, 1);
+        // Symphonia clamps MP3 output to ±1.0, which still counts as clipping
+        assert_eq!(audio_file.channels[0].peak().get_value(), 0.0);
+        assert!(audio_file.channels[0].clipping_samples_ratio() > 0.01);
+    }
+
+    #[test]
+    fn vorbis_padding_from_the_last_granule_position() {
+        // libvorbis encode of a 1 second 44.1 kHz sine, the last packet decodes 508 frames past its end
+        let bytes = include_bytes!("../../tests/fixtures/sine_440hz_stereo.ogg").to_vec();
+
+        let (samples_per_channel, audio_file) = decode(bytes);
+
+        assert_eq!(samples_per_channel, Some(44100));
+        assert_eq!(audio_file.samples_per_channel, 44100);
+        assert_eq!(audio_file.duration, 1);
+    }
+
+    #[test]
+    fn aac_delay_and_padding_from_itunsmpb() {
+        let frames: Vec<Vec<u8>> = [316; 15].into_iter().chain([340; 15]).map(aac_frame).collect();
+        let expected = 30 * AAC_FRAME_LENGTH - 2112 - 1608;
+        let itunsmpb = format!(" 00000000 00000840 00000648 {expected:016X} 00000000 00000000");
+
+        let (samples_per_channel, audio_file) = decode(m4a(&frames, &itunsmpb));
+
+        assert_eq!(samples_per_channel, Some(expected));
+        assert_eq!(audio_file.samples_per_channel as u64, expected);
+        assert_eq!(audio_file.duration, 0);
+        // The AAC decoder keeps the overs, they read above 0 dBFS
+        assert!(audio_file.channels[0].peak().get_value() > 1.0);
+        assert!(audio_file.channels[0].clipping_samples_ratio() > 0.01);
+    }
+
+    #[cfg(feature = "opus")]
+    fn ogg_crc(data: &[u8]) -> u32 {
+        data.iter().fold(0u32, |crc, &byte| {
+            (0..8).fold(crc ^ (u32::from(byte) << 24), |crc, _| {
+                if crc & 0x8000_0000 == 0 { crc << 1 } else { (crc << 1) ^ 0x04C1_1DB7 }
+            })
+        })
+    }
+
+    // One page per packet group, `flags` 2 opens the stream and 4 closes it
+    #[cfg(feature = "opus")]
+    fn ogg_page(sequence: u32, granule_position: u64, flags: u8, packets: &[Vec<u8>]) -> Vec<u8> {
+        let lacing: Vec<u8> = packets
+            .iter()
+            .flat_map(|packet| {
+                let mut values = vec![255; packet.len() / 255];
+                values.push((packet.len() % 255) as u8);
+                values
+            })
+            .collect();
+
+        let mut page = [b"OggS".as_slice(), &[0, flags], &granule_position.to_le_bytes(), &1u32.to_le_bytes()].concat();
+        page.extend_from_slice(&sequence.to_le_bytes());
+        page.extend_from_slice(&[0; 4]);
+        page.push(lacing.len() as u8);
+        page.extend_from_slice(&lacing);
+        page.extend(packets.concat());
+
+        let crc = ogg_crc(&page);
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    #[cfg(feature = "opus")]
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn opus_pre_skip_and_end_padding() {
+        use audiopus::{Application, Channels as OpusChannels, SampleRate, coder::Encoder};
+
+        const PACKET_FRAMES: usize = 960;
+        const LENGTH: usize = 48123;
+
+        let encoder = Encoder::new(SampleRate::Hz48000, OpusChannels::Stereo, Application::Audio).unwrap();
+        let pre_skip = encoder.lookahead().unwrap() as usize;
+
+        // Half a second of a quiet 1 kHz sine, then one driven 4 dB past full scale
+        let mut input: Vec<f32> = (0..pre_skip + LENGTH)
+            .flat_map(|index| {
+                let level = if index < 24000 { 0.25 } else { 1.6 };
+                let sample = level * (2.0 * std::f32::consts::PI * 1000.0 * index as f32 / 48000.0).sin();
+                [sample, sample]
+            })
+            .collect();
+        let packet_count = input.len().div_ceil(2 * PACKET_FRAMES);
+        input.resize(packet_count * 2 * PACKET_FRAMES, 0.0);
+
+        let mut output = vec![0; 4000];
+        let packets: Vec<Vec<u8>> = input
+            .chunks(2 * PACKET_FRAMES)
+            .map(|block| {
+                let length = encoder.encode_float(block, &mut output).unwrap();
+                output[..length].to_vec()
+            })
+            .collect();
+
+        let head = [b"OpusHead".as_slice(), &[1, 2], &(pre_skip as u16).to_le_bytes(), &48000u32.to_le_bytes(), &[0, 0, 0]].concat();
+        let tags = [b"OpusTags".as_slice(), &4u32.to_le_bytes(), b"test", &0u32.to_le_bytes()].concat();
+
+        let mut bytes = [ogg_page(0, 0, 2, &[head]), ogg_page(1, 0, 0, &[tags])].concat();
+        for (index, group) in packets.chunks(10).enumerate() {
+            let is_last = (index + 1) * 10 >= packets.len();
+            let granule_position = if is_last { pre_skip + LENGTH } else { (index + 1) * 10 * PACKET_FRAMES };
+            bytes.extend(ogg_page(index as u32 + 2, granule_position as u64, if is_last { 4 } else { 0 }, group));
+        }
+
+        // The first output frame lines up with the first input frame
+        let mut decoder = LossyDecoder::new(Cursor::new(bytes.clone())).unwrap();
+        let block = decoder.next_block().unwrap().unwrap();
+        let error = block.iter().zip(&input).skip(200).map(|(decoded, sample)| (decoded - f64::from(*sample)).abs());
+        assert!(error.fold(0.0, f64::max) < 0.05);
+
+        let (samples_per_channel, audio_file) = decode(bytes);
+
+        assert_eq!(samples_per_channel, Some(LENGTH as u64));
+        assert_eq!(audio_file.samples_per_channel, LENGTH);
+        assert_eq!(audio_file.duration, 1);
+        // libopus does not clamp its float output
+        assert!(audio_file.channels[0].peak().get_value() > 3.0);
+        assert!(audio_file.channels[0].clipping_samples_ratio() > 0.1);
+    }
+
+    #[test]
+    fn reject_garbage() {
+        assert!(LossyDecoder::new(Cursor::new(b"OggS\0\0\0\0".to_vec())).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+// Opus packets out of the Ogg demuxer decoded by libopus, registered next to the symphonia
+// codecs. Only channel mapping family 0 (mono and stereo) is handled, the multichannel
+// families need the multistream API
+use std::sync::Mutex;
+
+use audiopus::{
+    Channels as OpusChannels, MutSignals, SampleRate,
+    coder::{Decoder as LibOpusDecoder, GenericCtl},
+    packet::Packet as OpusPacket,
+};
+use symphonia::core::{
+    audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec},
+    codecs::{CODEC_TYPE_OPUS, CodecDescriptor, CodecParameters, Decoder, DecoderOptions, FinalizeResult},
+    errors::{Result, decode_error, unsupported_error},
+    formats::Packet,
+    support_codec,
+};
+
+// Opus always decodes at 48 kHz, the longest packet holds 120 ms
+const SAMPLE_RATE: u32 = 48000;
+const MAX_PACKET_FRAMES: usize = 5760;
+
+pub struct OpusDecoder {
+    params: CodecParameters,
+    // Symphonia decoders are Sync, the libopus state is only Send
+    decoder: Mutex<LibOpusDecoder>,
+    channels: usize,
+    interleaved: Vec<f32>,
+    buffer: AudioBuffer<f32>,
+}
+
+impl Decoder for OpusDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        let mapping_family = params.extra_data.as_deref().and_then(|head| head.get(18).copied());
+        let (Some(layout), Some(0)) = (params.channels, mapping_family) else {
+            return unsupported_error("opus: only mono and stereo streams are supported");
+        };
+
+        let channels = layout.count();
+        let opus_channels = if channels == 1 { OpusChannels::Mono } else { OpusChannels::Stereo };
+
+        let Ok(decoder) = LibOpusDecoder::new(SampleRate::Hz48000, opus_channels) else {
+            return decode_error("opus: libopus rejected the stream parameters");
+        };
+
+        Ok(Self {
+            params: params.clone(),
+            decoder: Mutex::new(decoder),
+            channels,
+            interleaved: vec![0.0; MAX_PACKET_FRAMES * channels],
+            buffer: AudioBuffer::new(MAX_PACKET_FRAMES as u64, SignalSpec::new(SAMPLE_RATE, layout)),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_OPUS, "opus", "Opus")]
+    }
+
+    fn reset(&mut self) {
+        if let Ok(decoder) = self.decoder.get_mut() {
+            // Only fails on a corrupted decoder state, which the next packet reports
+            let _ = decoder.reset_state();
+        }
+        self.buffer.clear();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        let Ok(decoder) = self.decoder.get_mut() else {
+            return decode_error("opus: decoder state lost");
+        };
+        let input = OpusPacket::try_from(packet.buf());
+        let output = MutSignals::try_from(&mut self.interleaved);
+        let (Ok(input), Ok(output)) = (input, output) else {
+            return decode_error("opus: invalid packet");
+        };
+        let Ok(frames) = decoder.decode_float(Some(input), output, false) else {
+            return decode_error("opus: invalid packet");
+        };
+
+        self.buffer.clear();
+        self.buffer.render_reserved(Some(frames));
+        for channel in 0..self.channels {
+            let samples = self.interleaved.iter().skip(channel).step_by(self.channels);
+            for (sample, decoded) in self.buffer.chan_mut(channel).iter_mut().zip(samples) {
+                *sample = *decoded;
+            }
+        }
+
+        // The end of the last packet lies past the final granule position
+        self.buffer.trim(packet.trim_start() as usize, packet.trim_end() as usize);
+
+        Ok(self.buffer.as_audio_buffer_ref())
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        FinalizeResult::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buffer.as_audio_buffer_ref()
+    }
+}
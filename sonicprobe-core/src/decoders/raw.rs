@@ -0,0 +1,272 @@
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind, Read},
+    path::Path,
+};
+
+use crate::{
+    decoders::{AudioDecoder, SampleFormat, StreamInfo},
+    model::{Samples, bit_depth::BitDepth, sonicprobe_error::SonicProbeError},
+};
+
+const FRAMES_PER_BLOCK: usize = 4096;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+// Headerless interleaved PCM as described on the command line, e.g. "s24le"
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RawPcmFormat {
+    pub sample_format: SampleFormat,
+    pub bits_per_sample: u16,
+    pub endianness: Endianness,
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+impl RawPcmFormat {
+    /// # Errors
+    ///
+    /// Fails when `encoding` names no supported sample format, or when `channels` or `sample_rate`
+    /// is 0.
+    pub fn new(encoding: &str, channels: u16, sample_rate: u32) -> Result<Self, SonicProbeError> {
+        let (sample_format, bits_per_sample, endianness) =
+            parse_encoding(&encoding.to_ascii_lowercase()).ok_or_else(|| SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: format!(
+                    "unsupported raw sample format '{encoding}', expected one of s16le, s24le, s32le, f32le, f64le or their be variants"
+                ),
+            })?;
+
+        if channels == 0 {
+            return Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: "raw input needs at least one channel".to_owned(),
+            });
+        }
+
+        if sample_rate == 0 {
+            return Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: "raw input needs a sample rate greater than 0Hz".to_owned(),
+            });
+        }
+
+        Ok(Self { sample_format, bits_per_sample, endianness, channels, sample_rate })
+    }
+
+    const fn bytes_per_sample(self) -> usize {
+        self.bits_per_sample as usize / 8
+    }
+}
+
+pub struct RawPcmDecoder<R: Read> {
+    reader: R,
+    format: RawPcmFormat,
+    full_scale: f64,
+    finished: bool,
+    bytes: Vec<u8>,
+    buffer: Vec<Samples>,
+}
+
+impl RawPcmDecoder<BufReader<File>> {
+    /// # Errors
+    ///
+    /// Fails when the file cannot be opened, or for the same reasons as [`RawPcmDecoder::new`].
+    pub fn open<P: AsRef<Path>>(path: P, format: RawPcmFormat) -> Result<Self, SonicProbeError> {
+        let file = File::open(path)?;
+        Self::new(BufReader::new(file), format)
+    }
+}
+
+impl<R: Read> RawPcmDecoder<R> {
+    /// # Errors
+    ///
+    /// Fails when `format` holds an integer bit depth outside 4 to 32 bits.
+    pub fn new(reader: R, format: RawPcmFormat) -> Result<Self, SonicProbeError> {
+        let full_scale = match format.sample_format {
+            SampleFormat::Integer => BitDepth::new(u32::from(format.bits_per_sample))?.full_scale(),
+            SampleFormat::Float => 1.0,
+        };
+
+        Ok(Self {
+            reader,
+            format,
+            full_scale,
+            finished: false,
+            bytes: Vec::new(),
+            buffer: Vec::new(),
+        })
+    }
+
+    fn fill_bytes(&mut self) -> Result<(), SonicProbeError> {
+        let block_bytes = FRAMES_PER_BLOCK * usize::from(self.format.channels) * self.format.bytes_per_sample();
+        self.bytes.resize(block_bytes, 0);
+
+        let mut filled = 0;
+        while filled < block_bytes {
+            match self.reader.read(&mut self.bytes[filled..]) {
+                Ok(0) => {
+                    self.finished = true;
+                    break;
+                }
+                Ok(count) => filled += count,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        // A trailing partial frame cannot be attributed to every channel
+        let frame_bytes = usize::from(self.format.channels) * self.format.bytes_per_sample();
+        self.bytes.truncate(filled - filled % frame_bytes);
+
+        Ok(())
+    }
+}
+
+impl<R: Read> AudioDecoder for RawPcmDecoder<R> {
+    fn stream_info(&self) -> StreamInfo {
+        StreamInfo {
+            channels: self.format.channels,
+            sample_rate: self.format.sample_rate,
+            bits_per_sample: u32::from(self.format.bits_per_sample),
+            sample_format: self.format.sample_format,
+            samples_per_channel: None,
+        }
+    }
+
+    fn next_block(&mut self) -> Result<Option<&[Samples]>, SonicProbeError> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        self.fill_bytes()?;
+
+        let (format, full_scale) = (self.format, self.full_scale);
+        self.buffer.clear();
+        self.buffer.extend(
+            self.bytes
+                .chunks_exact(format.bytes_per_sample())
+                .map(|sample| decode_sample(format, sample) / full_scale),
+        );
+
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(&self.buffer))
+    }
+}
+
+fn decode_sample(format: RawPcmFormat, bytes: &[u8]) -> f64 {
+    let mut buffer = [0u8; 8];
+
+    match format.endianness {
+        Endianness::Big => buffer[..bytes.len()].copy_from_slice(bytes),
+        Endianness::Little => {
+            for (target, byte) in buffer.iter_mut().zip(bytes.iter().rev()) {
+                *target = *byte;
+            }
+        }
+    }
+
+    match (format.sample_format, bytes.len()) {
+        (SampleFormat::Float, 4) => f64::from(f32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]])),
+        (SampleFormat::Float, _) => f64::from_be_bytes(buffer),
+        (SampleFormat::Integer, _) => {
+            let value = i32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+            f64::from(value >> (32 - format.bits_per_sample))
+        }
+    }
+}
+
+fn parse_encoding(encoding: &str) -> Option<(SampleFormat, u16, Endianness)> {
+    let (encoding, endianness) = if let Some(rest) = encoding.strip_suffix("le") {
+        (rest, Endianness::Little)
+    } else {
+        (encoding.strip_suffix("be")?, Endianness::Big)
+    };
+
+    match encoding {
+        "s16" => Some((SampleFormat::Integer, 16, endianness)),
+        "s24" => Some((SampleFormat::Integer, 24, endianness)),
+        "s32" => Some((SampleFormat::Integer, 32, endianness)),
+        "f32" => Some((SampleFormat::Float, 32, endianness)),
+        "f64" => Some((SampleFormat::Float, 64, endianness)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn decode_all(bytes: Vec<u8>, format: RawPcmFormat) -> Vec<f64> {
+        let mut decoder = RawPcmDecoder::new(Cursor::new(bytes), format).unwrap();
+        let mut samples = Vec::new();
+
+        while let Some(block) = decoder.next_block().unwrap() {
+            samples.extend_from_slice(block);
+        }
+
+        samples
+    }
+
+    #[test]
+    fn parse_formats() {
+        let format = RawPcmFormat::new("S24BE", 2, 48000).unwrap();
+
+        assert_eq!(format.sample_format, SampleFormat::Integer);
+        assert_eq!(format.bits_per_sample, 24);
+        assert_eq!(format.endianness, Endianness::Big);
+        assert_eq!(RawPcmFormat::new("f64le", 1, 8000).unwrap().sample_format, SampleFormat::Float);
+    }
+
+    #[test]
+    fn reject_invalid_formats() {
+        assert!(RawPcmFormat::new("s20le", 2, 44100).is_err());
+        assert!(RawPcmFormat::new("s16", 2, 44100).is_err());
+        assert!(RawPcmFormat::new("s16le", 0, 44100).is_err());
+        assert!(RawPcmFormat::new("s16le", 2, 0).is_err());
+    }
+
+    #[test]
+    fn signed_16_bit_little_endian() {
+        let bytes: Vec<u8> = [32767i16, -32767, 0, 1].iter().flat_map(|value| value.to_le_bytes()).collect();
+        let samples = decode_all(bytes, RawPcmFormat::new("s16le", 2, 44100).unwrap());
+
+        assert_eq!(samples, vec![1.0, -1.0, 0.0, 1.0 / 32767.0]);
+    }
+
+    #[test]
+    fn signed_24_bit_big_endian() {
+        let bytes = vec![0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        let samples = decode_all(bytes, RawPcmFormat::new("s24be", 2, 96000).unwrap());
+
+        assert_eq!(samples, vec![1.0, -1.0 / 8_388_607.0]);
+    }
+
+    #[test]
+    fn float_formats() {
+        let little: Vec<u8> = [0.5f32, -0.25].iter().flat_map(|value| value.to_le_bytes()).collect();
+        let big: Vec<u8> = [0.5f64, -0.25].iter().flat_map(|value| value.to_be_bytes()).collect();
+
+        assert_eq!(decode_all(little, RawPcmFormat::new("f32le", 1, 48000).unwrap()), vec![0.5, -0.25]);
+        assert_eq!(decode_all(big, RawPcmFormat::new("f64be", 1, 48000).unwrap()), vec![0.5, -0.25]);
+    }
+
+    #[test]
+    fn trailing_partial_frame_is_dropped() {
+        let bytes: Vec<u8> = (0..20001i16).flat_map(|value| (value % 1000).to_le_bytes()).collect();
+        let samples = decode_all(bytes, RawPcmFormat::new("s16le", 2, 44100).unwrap());
+
+        assert_eq!(samples.len(), 20000);
+    }
+}
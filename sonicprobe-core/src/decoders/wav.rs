@@ -0,0 +1,583 @@
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind, Read},
+    path::Path,
+};
+
+use crate::{
+    decoders::{AudioDecoder, SampleFormat, StreamInfo},
+    model::{
+        Samples,
+        bit_depth::BitDepth,
+        channel_layout::{ChannelLabel, default_layout, layout_from_mask},
+        sonicprobe_error::SonicProbeError,
+    },
+};
+
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+// RF64 placeholder, also written by encoders streaming to a pipe
+const UNKNOWN_SIZE: u32 = 0xFFFF_FFFF;
+
+const FRAMES_PER_BLOCK: usize = 4096;
+
+#[derive(Clone, Copy, Debug)]
+pub struct WavSpec {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub container_bits: u16,
+    pub sample_format: SampleFormat,
+    pub channel_mask: u32,
+    pub samples: Option<u64>,
+}
+
+pub struct WavReader<R: Read> {
+    reader: R,
+    spec: WavSpec,
+    // `None` while streaming a data chunk of unknown size, read up to the end of the input
+    remaining_bytes: Option<u64>,
+    full_scale: f64,
+    buffer: Vec<Samples>,
+}
+
+impl WavReader<BufReader<File>> {
+    /// # Errors
+    ///
+    /// Fails when the file cannot be opened, or for the same reasons as [`WavReader::new`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SonicProbeError> {
+        let file = File::open(path)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> WavReader<R> {
+    /// # Errors
+    ///
+    /// Fails when the header cannot be read, is not a RIFF, RF64 or BW64 WAVE header, holds a
+    /// malformed or unsupported `fmt ` or `ds64` chunk, or when no `fmt ` chunk precedes the data.
+    pub fn new(mut reader: R) -> Result<Self, SonicProbeError> {
+        let riff_id = read_id(&mut reader)?;
+        let _riff_size = read_u32(&mut reader)?;
+        let wave_id = read_id(&mut reader)?;
+
+        let is_rf64 = match &riff_id {
+            b"RIFF" => false,
+            b"RF64" | b"BW64" => true,
+            _ => return Err(format_error("missing RIFF/RF64 header")),
+        };
+
+        if &wave_id != b"WAVE" {
+            return Err(format_error("missing WAVE identifier"));
+        }
+
+        let mut ds64_data_size: Option<u64> = None;
+        let mut spec: Option<WavSpec> = None;
+
+        loop {
+            let chunk_id = read_id(&mut reader)?;
+            let chunk_size = read_u32(&mut reader)?;
+
+            match &chunk_id {
+                b"ds64" => {
+                    let Some(extra_size) = u64::from(chunk_size).checked_sub(16) else {
+                        return Err(format_error("ds64 chunk too short"));
+                    };
+                    let _riff_size = read_u64(&mut reader)?;
+                    ds64_data_size = Some(read_u64(&mut reader)?);
+                    skip_bytes(&mut reader, extra_size)?;
+                }
+                b"fmt " => {
+                    spec = Some(read_fmt_chunk(&mut reader, chunk_size)?);
+                }
+                b"data" => {
+                    let Some(mut spec) = spec else {
+                        return Err(format_error("data chunk found before fmt chunk"));
+                    };
+
+                    let data_size = match (is_rf64, chunk_size, ds64_data_size) {
+                        (true, UNKNOWN_SIZE, Some(size)) => Some(size),
+                        (true, UNKNOWN_SIZE, None) => {
+                            return Err(format_error("RF64 file without ds64 chunk"));
+                        }
+                        (false, UNKNOWN_SIZE, _) => None,
+                        _ => Some(u64::from(chunk_size)),
+                    };
+
+                    let frame_size = u64::from(spec.container_bits / 8) * u64::from(spec.channels);
+                    spec.samples = data_size.map(|size| size / frame_size);
+
+                    let full_scale = match spec.sample_format {
+                        SampleFormat::Integer => BitDepth::new(u32::from(spec.bits_per_sample))?.full_scale(),
+                        SampleFormat::Float => 1.0,
+                    };
+
+                    return Ok(Self {
+                        reader,
+                        spec,
+                        remaining_bytes: data_size.map(|size| size - (size % frame_size)),
+                        full_scale,
+                        buffer: Vec::new(),
+                    });
+                }
+                _ => skip_bytes(&mut reader, u64::from(chunk_size) + u64::from(chunk_size % 2))?,
+            }
+        }
+    }
+
+    pub const fn spec(&self) -> &WavSpec {
+        &self.spec
+    }
+
+    pub fn samples(&mut self) -> WavSamples<'_, R> {
+        WavSamples { wav_reader: self }
+    }
+
+    fn read_sample(&mut self) -> Result<Option<f64>, SonicProbeError> {
+        let bytes_per_sample = usize::from(self.spec.container_bits / 8);
+        let mut buffer = [0u8; 8];
+
+        match (self.reader.read_exact(&mut buffer[..bytes_per_sample]), self.remaining_bytes.as_mut()) {
+            (Ok(()), Some(remaining)) => *remaining -= bytes_per_sample as u64,
+            (Ok(()), None) => {}
+            (Err(error), None) if error.kind() == ErrorKind::UnexpectedEof => {
+                self.remaining_bytes = Some(0);
+                return Ok(None);
+            }
+            (Err(error), _) => return Err(error.into()),
+        }
+
+        Ok(Some(self.decode_sample(buffer)))
+    }
+
+    fn decode_sample(&self, buffer: [u8; 8]) -> f64 {
+        let bytes_per_sample = usize::from(self.spec.container_bits / 8);
+
+        match (self.spec.sample_format, bytes_per_sample) {
+            (SampleFormat::Float, 4) => {
+                let value = f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+                f64::from(value)
+            }
+            (SampleFormat::Float, _) => f64::from_le_bytes(buffer),
+            (SampleFormat::Integer, 1) => f64::from(i16::from(buffer[0]) - 128),
+            (SampleFormat::Integer, _) => {
+                let shift = 32 - self.spec.container_bits;
+                let container_value = i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) << shift >> shift;
+                let padding_bits = self.spec.container_bits - self.spec.bits_per_sample;
+
+                f64::from(container_value >> padding_bits)
+            }
+        }
+    }
+}
+
+impl<R: Read> AudioDecoder for WavReader<R> {
+    fn stream_info(&self) -> StreamInfo {
+        StreamInfo {
+            channels: self.spec.channels,
+            sample_rate: self.spec.sample_rate,
+            bits_per_sample: u32::from(self.spec.bits_per_sample),
+            sample_format: self.spec.sample_format,
+            samples_per_channel: self.spec.samples,
+        }
+    }
+
+    fn channel_layout(&self) -> Vec<ChannelLabel> {
+        match self.spec.channel_mask {
+            0 => default_layout(self.spec.channels),
+            mask => layout_from_mask(mask, self.spec.channels),
+        }
+    }
+
+    fn next_block(&mut self) -> Result<Option<&[Samples]>, SonicProbeError> {
+        let full_scale = self.full_scale;
+        let channels = usize::from(self.spec.channels);
+        let block_size = FRAMES_PER_BLOCK * channels;
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+
+        while buffer.len() < block_size && self.remaining_bytes != Some(0) {
+            match self.read_sample()? {
+                Some(sample) => buffer.push(sample / full_scale),
+                None => break,
+            }
+        }
+
+        // A stream of unknown size may be cut in the middle of a frame
+        buffer.truncate(buffer.len() - buffer.len() % channels);
+        self.buffer = buffer;
+
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(&self.buffer))
+    }
+}
+
+pub struct WavSamples<'a, R: Read> {
+    wav_reader: &'a mut WavReader<R>,
+}
+
+impl<R: Read> Iterator for WavSamples<'_, R> {
+    type Item = Result<f64, SonicProbeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.wav_reader.remaining_bytes == Some(0) {
+            return None;
+        }
+
+        self.wav_reader.read_sample().transpose()
+    }
+}
+
+fn read_fmt_chunk<R: Read>(reader: &mut R, chunk_size: u32) -> Result<WavSpec, SonicProbeError> {
+    if chunk_size < 16 {
+        return Err(format_error("fmt chunk is too short"));
+    }
+
+    let format_tag = read_u16(reader)?;
+    let channels = read_u16(reader)?;
+    let sample_rate = read_u32(reader)?;
+    let _byte_rate = read_u32(reader)?;
+    let block_align = read_u16(reader)?;
+    let bits_per_sample = read_u16(reader)?;
+
+    let mut consumed = 16u32;
+    let mut valid_bits = bits_per_sample;
+    let mut channel_mask = 0u32;
+    let mut sub_format = format_tag;
+
+    if format_tag == WAVE_FORMAT_EXTENSIBLE {
+        if chunk_size < 40 {
+            return Err(format_error("WAVE_FORMAT_EXTENSIBLE fmt chunk is too short"));
+        }
+
+        let _extension_size = read_u16(reader)?;
+        valid_bits = read_u16(reader)?;
+        channel_mask = read_u32(reader)?;
+        sub_format = read_u16(reader)?;
+        skip_bytes(reader, 14)?;
+        consumed = 40;
+    }
+
+    skip_bytes(reader, u64::from(chunk_size - consumed) + u64::from(chunk_size % 2))?;
+
+    if channels == 0 {
+        return Err(format_error("fmt chunk declares zero channels"));
+    }
+
+    let container_bits = (block_align / channels) * 8;
+
+    if valid_bits == 0 || valid_bits > container_bits {
+        valid_bits = container_bits;
+    }
+
+    let sample_format = match (sub_format, container_bits) {
+        (WAVE_FORMAT_PCM, 8 | 16 | 24 | 32) => SampleFormat::Integer,
+        (WAVE_FORMAT_IEEE_FLOAT, 32 | 64) => SampleFormat::Float,
+        _ => {
+            return Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: format!(
+                    "unsupported WAV encoding: format tag {sub_format:#06x} with {container_bits} bit samples"
+                ),
+            })
+        }
+    };
+
+    Ok(WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: valid_bits,
+        container_bits,
+        sample_format,
+        channel_mask,
+        samples: None,
+    })
+}
+
+fn read_id<R: Read>(reader: &mut R) -> Result<[u8; 4], SonicProbeError> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16, SonicProbeError> {
+    let mut buffer = [0u8; 2];
+    reader.read_exact(&mut buffer)?;
+    Ok(u16::from_le_bytes(buffer))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, SonicProbeError> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, SonicProbeError> {
+    let mut buffer = [0u8; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+fn skip_bytes<R: Read>(reader: &mut R, count: u64) -> Result<(), SonicProbeError> {
+    let skipped = std::io::copy(&mut reader.take(count), &mut std::io::sink())?;
+
+    if skipped != count {
+        return Err(format_error("unexpected end of file"));
+    }
+
+    Ok(())
+}
+
+fn format_error(message: &str) -> SonicProbeError {
+    SonicProbeError {
+        location: format!("{}:{}", file!(), line!()),
+        message: format!("invalid WAV stream: {message}"),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn fmt_chunk(format_tag: u16, channels: u16, sample_rate: u32, bits: u16) -> Vec<u8> {
+        let block_align = channels * bits / 8;
+        let mut chunk = b"fmt ".to_vec();
+        chunk.extend_from_slice(&16u32.to_le_bytes());
+        chunk.extend_from_slice(&format_tag.to_le_bytes());
+        chunk.extend_from_slice(&channels.to_le_bytes());
+        chunk.extend_from_slice(&sample_rate.to_le_bytes());
+        chunk.extend_from_slice(&(sample_rate * u32::from(block_align)).to_le_bytes());
+        chunk.extend_from_slice(&block_align.to_le_bytes());
+        chunk.extend_from_slice(&bits.to_le_bytes());
+        chunk
+    }
+
+    fn extensible_fmt_chunk(channels: u16, container_bits: u16, valid_bits: u16, sub_format: u16) -> Vec<u8> {
+        let block_align = channels * container_bits / 8;
+        let mut chunk = b"fmt ".to_vec();
+        chunk.extend_from_slice(&40u32.to_le_bytes());
+        chunk.extend_from_slice(&WAVE_FORMAT_EXTENSIBLE.to_le_bytes());
+        chunk.extend_from_slice(&channels.to_le_bytes());
+        chunk.extend_from_slice(&48000u32.to_le_bytes());
+        chunk.extend_from_slice(&(48000 * u32::from(block_align)).to_le_bytes());
+        chunk.extend_from_slice(&block_align.to_le_bytes());
+        chunk.extend_from_slice(&container_bits.to_le_bytes());
+        chunk.extend_from_slice(&22u16.to_le_bytes());
+        chunk.extend_from_slice(&valid_bits.to_le_bytes());
+        chunk.extend_from_slice(&3u32.to_le_bytes());
+        chunk.extend_from_slice(&sub_format.to_le_bytes());
+        chunk.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71]);
+        chunk
+    }
+
+    fn riff_file(fmt: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&u32::try_from(4 + fmt.len() + 8 + data.len()).unwrap().to_le_bytes());
+        file.extend_from_slice(b"WAVE");
+        file.extend_from_slice(b"LIST");
+        file.extend_from_slice(&3u32.to_le_bytes());
+        file.extend_from_slice(&[1, 2, 3, 0]);
+        file.extend_from_slice(fmt);
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+        file.extend_from_slice(data);
+        file
+    }
+
+    fn read_all(bytes: Vec<u8>) -> (WavSpec, Vec<f64>) {
+        let mut reader = WavReader::new(Cursor::new(bytes)).unwrap();
+        let samples = reader.samples().collect::<Result<Vec<f64>, _>>().unwrap();
+        (*reader.spec(), samples)
+    }
+
+    #[test]
+    fn pcm_16_bit() {
+        let data: Vec<u8> = [1i16, -1, 32767, -32768]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let (spec, samples) = read_all(riff_file(&fmt_chunk(WAVE_FORMAT_PCM, 2, 44100, 16), &data));
+
+        assert_eq!(spec.channels, 2);
+        assert_eq!(spec.sample_rate, 44100);
+        assert_eq!(spec.bits_per_sample, 16);
+        assert_eq!(spec.sample_format, SampleFormat::Integer);
+        assert_eq!(spec.samples, Some(2));
+        assert_eq!(samples, vec![1.0, -1.0, 32767.0, -32768.0]);
+    }
+
+    #[test]
+    fn pcm_8_bit_is_unsigned() {
+        let (_, samples) = read_all(riff_file(&fmt_chunk(WAVE_FORMAT_PCM, 2, 44100, 8), &[128, 255, 0, 129]));
+
+        assert_eq!(samples, vec![0.0, 127.0, -128.0, 1.0]);
+    }
+
+    #[test]
+    fn pcm_24_bit() {
+        let data = [0xFF, 0xFF, 0x7F, 0x00, 0x00, 0x80, 0x01, 0x00, 0x00, 0xFF, 0xFF, 0xFF];
+        let (spec, samples) = read_all(riff_file(&fmt_chunk(WAVE_FORMAT_PCM, 2, 96000, 24), &data));
+
+        assert_eq!(spec.bits_per_sample, 24);
+        assert_eq!(samples, vec![8_388_607.0, -8_388_608.0, 1.0, -1.0]);
+    }
+
+    #[test]
+    fn pcm_32_bit() {
+        let data: Vec<u8> = [i32::MAX, i32::MIN]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let (_, samples) = read_all(riff_file(&fmt_chunk(WAVE_FORMAT_PCM, 2, 48000, 32), &data));
+
+        assert_eq!(samples, vec![2_147_483_647.0, -2_147_483_648.0]);
+    }
+
+    #[test]
+    fn ieee_float_32_bit() {
+        let data: Vec<u8> = [0.5f32, -1.25]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let (spec, samples) = read_all(riff_file(&fmt_chunk(WAVE_FORMAT_IEEE_FLOAT, 2, 48000, 32), &data));
+
+        assert_eq!(spec.sample_format, SampleFormat::Float);
+        assert_eq!(samples, vec![0.5, -1.25]);
+    }
+
+    #[test]
+    fn ieee_float_64_bit() {
+        let data: Vec<u8> = [0.1f64, -0.2]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let (spec, samples) = read_all(riff_file(&fmt_chunk(WAVE_FORMAT_IEEE_FLOAT, 2, 48000, 64), &data));
+
+        assert_eq!(spec.container_bits, 64);
+        assert_eq!(samples, vec![0.1, -0.2]);
+    }
+
+    #[test]
+    fn extensible_24_in_32_bit_container() {
+        let data: Vec<u8> = [0x7FFF_FF00i32, -256]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let (spec, samples) = read_all(riff_file(&extensible_fmt_chunk(2, 32, 24, WAVE_FORMAT_PCM), &data));
+
+        assert_eq!(spec.bits_per_sample, 24);
+        assert_eq!(spec.container_bits, 32);
+        assert_eq!(spec.channel_mask, 3);
+        assert_eq!(samples, vec![8_388_607.0, -1.0]);
+    }
+
+    #[test]
+    fn extensible_float() {
+        let data: Vec<u8> = [0.75f32, 0.25]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let (spec, samples) = read_all(riff_file(&extensible_fmt_chunk(2, 32, 32, WAVE_FORMAT_IEEE_FLOAT), &data));
+
+        assert_eq!(spec.sample_format, SampleFormat::Float);
+        assert_eq!(samples, vec![0.75, 0.25]);
+    }
+
+    #[test]
+    fn rf64_uses_ds64_data_size() {
+        let data: Vec<u8> = [100i16, -100, 200, -200]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let mut file = b"RF64".to_vec();
+        file.extend_from_slice(&UNKNOWN_SIZE.to_le_bytes());
+        file.extend_from_slice(b"WAVE");
+        file.extend_from_slice(b"ds64");
+        file.extend_from_slice(&28u32.to_le_bytes());
+        file.extend_from_slice(&0u64.to_le_bytes());
+        file.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        file.extend_from_slice(&2u64.to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&fmt_chunk(WAVE_FORMAT_PCM, 2, 44100, 16));
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&UNKNOWN_SIZE.to_le_bytes());
+        file.extend_from_slice(&data);
+
+        let (spec, samples) = read_all(file);
+
+        assert_eq!(spec.samples, Some(2));
+        assert_eq!(samples, vec![100.0, -100.0, 200.0, -200.0]);
+    }
+
+    #[test]
+    fn truncated_ds64_chunk() {
+        let mut file = b"RF64".to_vec();
+        file.extend_from_slice(&UNKNOWN_SIZE.to_le_bytes());
+        file.extend_from_slice(b"WAVE");
+        file.extend_from_slice(b"ds64");
+        file.extend_from_slice(&8u32.to_le_bytes());
+        file.extend_from_slice(&0u64.to_le_bytes());
+        file.extend_from_slice(&fmt_chunk(WAVE_FORMAT_PCM, 2, 44100, 16));
+
+        assert!(WavReader::new(Cursor::new(file)).is_err());
+    }
+
+    #[test]
+    fn streamed_data_of_unknown_size() {
+        let data: Vec<u8> = [100i16, -100, 200, -200, 300]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let mut file = riff_file(&fmt_chunk(WAVE_FORMAT_PCM, 2, 44100, 16), &data);
+        let data_size_offset = file.len() - data.len() - 4;
+        file[data_size_offset..data_size_offset + 4].copy_from_slice(&UNKNOWN_SIZE.to_le_bytes());
+
+        let mut reader = WavReader::new(Cursor::new(file)).unwrap();
+        let block = reader.next_block().unwrap().unwrap().to_vec();
+
+        assert_eq!(reader.stream_info().samples_per_channel, None);
+        assert_eq!(block, [100.0, -100.0, 200.0, -200.0].map(|value| value / 32767.0));
+        assert!(reader.next_block().unwrap().is_none());
+    }
+
+    #[test]
+    fn decoder_blocks_are_normalized() {
+        let data: Vec<u8> = (0..10000i16)
+            .flat_map(|value| (value - 5000).to_le_bytes())
+            .collect();
+        let mut reader = WavReader::new(Cursor::new(riff_file(&fmt_chunk(WAVE_FORMAT_PCM, 2, 44100, 16), &data))).unwrap();
+        let mut decoded = Vec::new();
+
+        while let Some(block) = reader.next_block().unwrap() {
+            assert!(block.len() <= FRAMES_PER_BLOCK * 2);
+            decoded.extend_from_slice(block);
+        }
+
+        let expected: Vec<f64> = (0..10000i16).map(|value| f64::from(value - 5000) / 32767.0).collect();
+        assert_eq!(decoded, expected);
+        assert_eq!(reader.stream_info().samples_per_channel, Some(5000));
+    }
+
+    #[test]
+    fn unsupported_format_tag() {
+        let file = riff_file(&fmt_chunk(0x0055, 2, 44100, 16), &[0, 0, 0, 0]);
+
+        assert!(WavReader::new(Cursor::new(file)).is_err());
+    }
+
+    #[test]
+    fn not_a_wav_file() {
+        let file = b"fLaC\0\0\0\x22".to_vec();
+
+        assert!(WavReader::new(Cursor::new(file)).is_err());
+    }
+}
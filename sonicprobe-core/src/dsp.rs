@@ -2,29 +2,58 @@ pub mod analysis;
 mod upscaler;
 
 use crate::{
-    analysis::peak::update_peak_value, dsp::{analysis::clipping::update_clipping_count, upscaler::Upscaler}, model::{Signal, decibel::Decibel, frequency::Frequency, sonicprobe_error::SonicProbeError}
+    analysis::peak::update_peak_value, dsp::{analysis::clipping::update_clipping_count, upscaler::Upscaler}, model::{decibel::Decibel, frequency::Frequency}
 };
 
-pub fn upsample_chain(
-    source: &Signal,
-    source_sample_rate: Frequency,
-) -> Result<(Decibel, u64), SonicProbeError> {
-    let mut upscaler = Upscaler::new(source, source_sample_rate)?;
+pub struct TruePeakMeter {
+    upscaler: Upscaler,
+    peak: f64,
+    clipping_samples_count: u64,
+}
+
+impl TruePeakMeter {
+    #[must_use]
+    pub fn new(source_sample_rate: Frequency) -> Self {
+        Self {
+            upscaler: Upscaler::new(source_sample_rate),
+            peak: f64::MIN,
+            clipping_samples_count: 0,
+        }
+    }
 
-    let mut peak = f64::MIN;
-    let mut clipping_samples_count = 0u64;
+    // Returns the loudest of the interpolated values produced for this sample
+    #[inline]
+    pub fn push_sample(&mut self, sample: f64) -> f64 {
+        let mut loudest: f64 = 0.0;
 
-    while let Some(sample) = upscaler.next_sample() {
-        match update_clipping_count(&clipping_samples_count, sample) {
-            Some(result) => clipping_samples_count = result,
-            None => {}
+        for sample in self.upscaler.push_sample(sample) {
+            record(&mut self.peak, &mut self.clipping_samples_count, *sample);
+            loudest = loudest.max(sample.abs());
         }
 
-        match update_peak_value(&peak, &sample.abs()) {
-            Some(result) => peak = result,
-            None => {}
+        loudest
+    }
+
+    // Interpolates the end of the stream, call once after the last sample
+    pub fn flush(&mut self) {
+        for sample in self.upscaler.flush() {
+            record(&mut self.peak, &mut self.clipping_samples_count, sample);
         }
     }
 
-    Ok((Decibel::new(peak), clipping_samples_count))
+    #[must_use]
+    pub fn value(&self) -> (Decibel, u64) {
+        (Decibel::new(self.peak), self.clipping_samples_count)
+    }
+}
+
+#[inline]
+fn record(peak: &mut f64, clipping_samples_count: &mut u64, sample: f64) {
+    if let Some(result) = update_clipping_count(clipping_samples_count, &sample) {
+        *clipping_samples_count = result;
+    }
+
+    if let Some(result) = update_peak_value(peak, &sample.abs()) {
+        *peak = result;
+    }
 }
@@ -1,8 +1,12 @@
 pub mod clipping;
 pub mod dc_offset;
 pub mod dynamic_range;
+pub mod rms_range;
 pub mod peak;
 pub mod root_mean_square;
 pub mod stereo_correlation;
 pub mod bit_depth;
 pub mod zero_crossing_rate;
+pub mod loudness;
+pub mod spectrum;
+pub mod lossy_transcode;
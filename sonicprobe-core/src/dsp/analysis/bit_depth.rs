@@ -1,66 +1,128 @@
 use crate::model::{
-    MAX_8_BIT, MAX_16_BIT, MAX_24_BIT, MAX_32_BIT, bit_depth::BitDepth,
-    sonicprobe_error::SonicProbeError, stereo_signal::StereoSignal,
+    bit_depth::{BitDepth, DepthUsage},
+    sonicprobe_error::SonicProbeError,
 };
 
-#[inline]
-pub fn calculate_true_depth(source: &StereoSignal) -> Result<u8, SonicProbeError> {
-    let factor = match source.depth {
-        BitDepth::Legacy => MAX_8_BIT,
-        BitDepth::CdStandard => MAX_16_BIT,
-        BitDepth::Professional => MAX_24_BIT,
-        BitDepth::StudioMaster => MAX_32_BIT,
-    };
+pub struct TrueDepthMeter {
+    depth: BitDepth,
+    integer_depth: u8,
+    float_integer_depth: i32,
+    mantissa_bits: u32,
+}
 
-    let mut actual_depth = 0u8;
+impl TrueDepthMeter {
+    #[must_use]
+    pub const fn new(depth: BitDepth) -> Self {
+        Self {
+            depth,
+            integer_depth: 0,
+            float_integer_depth: 0,
+            mantissa_bits: 0,
+        }
+    }
 
-    for &sample in source.left.iter() {
-        if sample == 0.0 {
-            continue;
+    /// # Errors
+    ///
+    /// Only guards the conversion of the trailing zero count of an integer sample to `u8`,
+    /// which cannot fail for the supported depths of at most 32 bits.
+    #[inline]
+    pub fn push_sample(&mut self, sample: f64) -> Result<(), SonicProbeError> {
+        match self.depth {
+            BitDepth::Integer(bits) => self.push_integer_sample(sample, bits),
+            BitDepth::Float(_) => {
+                self.push_float_sample(sample);
+                Ok(())
+            }
         }
+    }
 
-        let reconstructed_value: i32 = unsafe { (sample * factor).trunc().to_int_unchecked() };
+    #[allow(clippy::cast_possible_truncation)]
+    fn push_integer_sample(&mut self, sample: f64, bits: u8) -> Result<(), SonicProbeError> {
+        if sample == 0.0 || self.integer_depth == bits {
+            return Ok(());
+        }
 
-        let sample_depth: u8 =
-            source.depth.to_bits() - u8::try_from(reconstructed_value.trailing_zeros())?;
+        // Saturates on decoded lossy streams beyond full scale, NaN reads as 0
+        let reconstructed_value = (sample * self.depth.full_scale()).round() as i32;
 
-        if sample_depth > actual_depth {
-            actual_depth = sample_depth;
-        }
+        let sample_depth: u8 = bits.saturating_sub(u8::try_from(reconstructed_value.trailing_zeros())?);
 
-        if actual_depth == source.depth.to_bits() {
-            break;
+        if sample_depth > self.integer_depth {
+            self.integer_depth = sample_depth;
         }
+
+        Ok(())
     }
 
-    for &sample in source.right.iter() {
-        if sample == 0.0 {
-            continue;
-        }
+    fn push_float_sample(&mut self, sample: f64) {
+        let Some((mantissa, exponent)) = decompose(sample) else {
+            return;
+        };
 
-        let reconstructed_value: i32 = unsafe { (sample * factor).trunc().to_int_unchecked() };
+        self.float_integer_depth = self.float_integer_depth.max(1 - exponent);
+        self.mantissa_bits = self.mantissa_bits.max(u64::BITS - mantissa.leading_zeros());
+    }
 
-        let sample_depth: u8 =
-            source.depth.to_bits() - u8::try_from(reconstructed_value.trailing_zeros())?;
+    // A float file holding n bit integer audio only ever uses multiples of 2^-(n-1),
+    // anything finer is reported as the widest mantissa in use
+    #[must_use]
+    pub fn value(&self) -> DepthUsage {
+        match self.depth {
+            BitDepth::Integer(_) => DepthUsage::Integer(self.integer_depth),
+            BitDepth::Float(bits) => {
+                let integer_limit = if bits == 32 { 24 } else { 32 };
 
-        if sample_depth > actual_depth {
-            actual_depth = sample_depth;
+                match u8::try_from(self.float_integer_depth) {
+                    Ok(depth) if depth <= integer_limit => DepthUsage::QuantisedFloat(depth),
+                    _ => DepthUsage::Mantissa(u8::try_from(self.mantissa_bits).unwrap_or(u8::MAX)),
+                }
+            }
         }
+    }
+}
 
-        if actual_depth == source.depth.to_bits() {
-            break;
-        }
+// Whole-signal form of the meter, kept for the tests
+#[cfg(test)]
+fn calculate_true_depth(source: &crate::model::audio_signal::AudioSignal) -> Result<DepthUsage, SonicProbeError> {
+    let mut meter = TrueDepthMeter::new(source.depth);
+
+    for &sample in source.channels.iter().flat_map(|channel| channel.iter()) {
+        meter.push_sample(sample)?;
     }
 
-    Ok(actual_depth)
+    Ok(meter.value())
+}
+
+// Splits a finite non-zero sample into an odd mantissa and its power of two
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_wrap)]
+fn decompose(sample: f64) -> Option<(u64, i32)> {
+    if sample == 0.0 || !sample.is_finite() {
+        return None;
+    }
+
+    let raw = sample.to_bits();
+    let biased_exponent = ((raw >> 52) & 0x7FF) as i32;
+    let fraction = raw & ((1 << 52) - 1);
+
+    let (mantissa, exponent) = if biased_exponent == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | (1 << 52), biased_exponent - 1075)
+    };
+
+    let shift = mantissa.trailing_zeros();
+
+    Some((mantissa >> shift, exponent + shift as i32))
 }
 
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
+    use super::{TrueDepthMeter, decompose};
     use crate::{
         dsp::analysis::bit_depth::calculate_true_depth,
-        model::{bit_depth::BitDepth, frequency::Frequency, stereo_signal::StereoSignal, MAX_16_BIT, MAX_8_BIT},
+        model::{audio_signal::AudioSignal, bit_depth::{BitDepth, DepthUsage}, channel_layout::default_layout, frequency::Frequency, MAX_16_BIT, MAX_8_BIT},
     };
 
     #[test]
@@ -74,15 +136,15 @@ mod tests {
             .map(|val: &u8| f64::from(*val) / MAX_8_BIT)
             .collect();
         let depth = BitDepth::new(8).unwrap();
-        let stereo = StereoSignal {
-            left,
-            right,
-            sample_rate: Frequency::CdQuality,
+        let stereo = AudioSignal {
+            channels: vec![left, right],
+            layout: default_layout(2),
+            sample_rate: Frequency::CD_QUALITY,
             depth
         };
         let res = calculate_true_depth(&stereo).unwrap();
 
-        assert_eq!(res, 8u8);
+        assert_eq!(res, DepthUsage::Integer(8u8));
     }
 
     #[test]
@@ -96,15 +158,15 @@ mod tests {
             .map(|val: &u16| f64::from(*val << 8) / MAX_16_BIT)
             .collect();
         let depth = BitDepth::new(16).unwrap();
-        let stereo = StereoSignal {
-            left,
-            right,
-            sample_rate: Frequency::CdQuality,
+        let stereo = AudioSignal {
+            channels: vec![left, right],
+            layout: default_layout(2),
+            sample_rate: Frequency::CD_QUALITY,
             depth
         };
         let res = calculate_true_depth(&stereo).unwrap();
 
-        assert_eq!(res, 8u8);
+        assert_eq!(res, DepthUsage::Integer(8u8));
     }
 
     #[test]
@@ -118,14 +180,101 @@ mod tests {
             .map(|val: &u16| f64::from(*val << 3) / MAX_16_BIT)
             .collect();
         let depth = BitDepth::new(16).unwrap();
-        let stereo = StereoSignal {
-            left,
-            right,
-            sample_rate: Frequency::CdQuality,
+        let stereo = AudioSignal {
+            channels: vec![left, right],
+            layout: default_layout(2),
+            sample_rate: Frequency::CD_QUALITY,
             depth
         };
         let res = calculate_true_depth(&stereo).unwrap();
 
-        assert_eq!(res, 15u8);
+        assert_eq!(res, DepthUsage::Integer(15u8));
+    }
+
+    #[test]
+    fn mono_data() {
+        let left = [10, 20, 30, 111]
+            .iter()
+            .map(|val: &u16| f64::from(*val << 4) / MAX_16_BIT)
+            .collect();
+        let depth = BitDepth::new(16).unwrap();
+        let mono = AudioSignal {
+            channels: vec![left],
+            layout: default_layout(1),
+            sample_rate: Frequency::CD_QUALITY,
+            depth
+        };
+        let res = calculate_true_depth(&mono).unwrap();
+
+        assert_eq!(res, DepthUsage::Integer(12u8));
+    }
+
+    #[test]
+    fn twenty_bit_data_with_padding() {
+        let depth = BitDepth::new(20).unwrap();
+        let left = [-524_272, 48, 16_000]
+            .iter()
+            .map(|val: &i32| f64::from(*val) / depth.full_scale())
+            .collect();
+        let signal = AudioSignal {
+            channels: vec![left],
+            layout: default_layout(1),
+            sample_rate: Frequency::CD_QUALITY,
+            depth
+        };
+        let res = calculate_true_depth(&signal).unwrap();
+
+        assert_eq!(res, DepthUsage::Integer(16u8));
+    }
+
+    fn float_signal(samples: &[f32]) -> AudioSignal {
+        AudioSignal {
+            channels: vec![samples.iter().map(|sample| f64::from(*sample)).collect()],
+            layout: default_layout(1),
+            sample_rate: Frequency::CD_QUALITY,
+            depth: BitDepth::FLOAT,
+        }
+    }
+
+    #[test]
+    fn float_holding_16_bit_audio() {
+        let samples: Vec<f32> = [-32768, -3, 0, 12345, 32767].iter().map(|val: &i16| f32::from(*val) / 32768.0).collect();
+        let res = calculate_true_depth(&float_signal(&samples)).unwrap();
+
+        assert_eq!(res, DepthUsage::QuantisedFloat(16));
+    }
+
+    #[test]
+    fn float_with_overs_holding_24_bit_audio() {
+        let samples = [1.5f32, -2.25, 1.0 / 8_388_608.0];
+        let res = calculate_true_depth(&float_signal(&samples)).unwrap();
+
+        assert_eq!(res, DepthUsage::QuantisedFloat(24));
+    }
+
+    #[test]
+    fn genuine_float_audio_uses_full_mantissa() {
+        let samples = [0.3f32, -0.7, 1.0e-6];
+        let res = calculate_true_depth(&float_signal(&samples)).unwrap();
+
+        assert_eq!(res, DepthUsage::Mantissa(24));
+    }
+
+    #[test]
+    fn nan_and_overs_in_integer_audio() {
+        let mut meter = TrueDepthMeter::new(BitDepth::new(16).unwrap());
+        for sample in [f64::NAN, 32768.0 / MAX_16_BIT, 256.0 / MAX_16_BIT] {
+            meter.push_sample(sample).unwrap();
+        }
+
+        assert_eq!(meter.value(), DepthUsage::Integer(8));
+    }
+
+    #[test]
+    fn decompose_values() {
+        assert_eq!(decompose(0.75), Some((3, -2)));
+        assert_eq!(decompose(-8.0), Some((1, 3)));
+        assert_eq!(decompose(0.0), None);
+        assert_eq!(decompose(f64::NAN), None);
     }
 }
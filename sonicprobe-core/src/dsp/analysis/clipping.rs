@@ -1,3 +1,4 @@
+#[must_use]
 pub fn update_clipping_count(current_count: &u64, sample: &f64) -> Option<u64> {
     if is_distorted(*sample) {
         return Some(*current_count + 1)
@@ -12,6 +13,9 @@ const fn is_distorted(sample: f64) -> bool {
 
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
+#[allow(clippy::unreadable_literal)]
+#[allow(clippy::bool_assert_comparison)]
+#[allow(clippy::semicolon_if_nothing_returned)]
 mod tests {
     use rand::Rng;
     use super::*;
@@ -97,6 +101,17 @@ mod tests {
         assert_eq!(result, Some(1));
     }
 
+    #[test]
+    fn update_clipping_count_decoded_overs() {
+        let result = update_clipping_count(&41, &1.83);
+
+        assert_eq!(result, Some(42));
+
+        let result = update_clipping_count(&41, &-2.4);
+
+        assert_eq!(result, Some(42));
+    }
+
     #[test]
     fn is_distorted_truthy() {
         let mut rng = rand::rng();
@@ -1,28 +1,55 @@
 use crate::{
-    floating_point_math::floating_point_utils::map_sum_lossless,
+    floating_point_math::floating_point_utils::LosslessSum,
     model::{sonicprobe_error::SonicProbeError, Signal},
 };
 
+#[derive(Clone, Copy, Default)]
+pub struct DcOffsetMeter {
+    sum: LosslessSum,
+    count: usize,
+}
+
+impl DcOffsetMeter {
+    #[inline]
+    pub fn push_sample(&mut self, sample: f64) {
+        self.sum.add(sample);
+        self.count += 1;
+    }
+
+    /// # Errors
+    ///
+    /// Fails when the sample count cannot be represented exactly as an `f64`.
+    #[allow(clippy::cast_sign_loss)]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn value(&self) -> Result<f64, SonicProbeError> {
+        let size = self.count as f64;
+        if (size as usize) != self.count {
+            return Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: format!(
+                    "cannot represent usize value {} exactly in f64",
+                    self.count
+                ),
+            });
+        }
+
+        Ok(self.sum.value() / size)
+    }
+}
 
+/// # Errors
+///
+/// Fails when the sample count cannot be represented exactly as an `f64`.
 #[inline]
-#[allow(clippy::cast_sign_loss)]
-#[allow(clippy::cast_precision_loss)]
-#[allow(clippy::cast_possible_truncation)]
 pub fn calculate_dc_offset(values: &Signal) -> Result<f64, SonicProbeError> {
-    let sum = map_sum_lossless(values, |x| x);
+    let mut meter = DcOffsetMeter::default();
 
-    let size = values.len() as f64;
-    if (size as usize) != values.len() {
-        return Err(SonicProbeError {
-            location: format!("{}:{}", file!(), line!()),
-            message: format!(
-                "cannot represent usize value {} exactly in f64",
-                values.len()
-            ),
-        });
+    for &value in values.iter() {
+        meter.push_sample(value);
     }
 
-    Ok(sum / size)
+    meter.value()
 }
 
 #[cfg(test)]
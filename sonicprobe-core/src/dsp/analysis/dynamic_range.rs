@@ -1,291 +1,219 @@
-use core::f64;
-use std::mem;
-
-use crate::{
-    analysis::root_mean_square::compute_root_mean_square, model::{decibel::Decibel, frequency::Frequency}, sonicprobe_error::SonicProbeError
-};
-
-const TARGET_SAMPLE_POPULATION_SHARE: usize = 20;
-
+use crate::model::{decibel::Decibel, frequency::Frequency};
+
+const BLOCK_SECONDS: usize = 3;
+// One block in five is the loudest 20%
+const LOUDEST_BLOCKS_DIVISOR: usize = 5;
+// The reference meter reads DR 0 below three blocks, partial last block included
+const MIN_BLOCKS: usize = 3;
+
+// Official DR as computed by the TT DR Offline meter and the foobar2000 DR meter:
+// √2 scaled RMS per 3 second block, RMS of the loudest 20% of the blocks and the
+// second highest block peak. The last partial block takes part in the ranking
+// with an RMS over its own length and counts as a block when sizing the 20%
 pub struct DynamicRangeMeter {
-    buffer: Vec<f64>,
-    buffer_size: usize,
-    next_insert_index: usize,
-    quiet_parts_rms: Vec<f64>,
-    loud_parts_rms: Vec<f64>
+    block_size: usize,
+    position: usize,
+    square_sum: f64,
+    peak: f64,
+    blocks_rms: Vec<f64>,
+    blocks_peak: Vec<f64>,
 }
 
 impl DynamicRangeMeter {
-    pub fn new(samples_count: &usize, sample_rate: &Frequency) -> DynamicRangeMeter {
-        let buffer_size = get_chunk_size(*sample_rate);
-        let target_population = get_target_population_count(samples_count, &buffer_size);
-
+    #[must_use]
+    pub const fn new(sample_rate: Frequency) -> Self {
         Self {
-            buffer: vec![0.0; buffer_size],
-            buffer_size,
-            next_insert_index: 0,
-            quiet_parts_rms: vec![f64::MAX; target_population],
-            loud_parts_rms: vec![f64::MIN; target_population]
+            block_size: sample_rate.to_hz() * BLOCK_SECONDS,
+            position: 0,
+            square_sum: 0.0,
+            peak: 0.0,
+            blocks_rms: Vec::new(),
+            blocks_peak: Vec::new(),
         }
     }
 
-    pub fn push_sample(&mut self, sample: &f64) -> Result<(), SonicProbeError>{
-        self.buffer[self.next_insert_index] = *sample;
-        self.next_insert_index += 1;
-
-        if self.next_insert_index == self.buffer_size {
-            let new_rms = compute_root_mean_square(&self.buffer)?;
-
-            update_quiet_rms_population(&new_rms, &mut self.quiet_parts_rms);
-            update_loud_rms_population(&new_rms, &mut self.loud_parts_rms);
-
-            self.next_insert_index = 0;
+    #[inline]
+    pub fn push_sample(&mut self, sample: f64) {
+        self.square_sum += sample * sample;
+        self.peak = self.peak.max(sample.abs());
+        self.position += 1;
+
+        if self.position == self.block_size {
+            self.blocks_rms.push(block_rms(self.square_sum, self.position));
+            self.blocks_peak.push(self.peak);
+            self.position = 0;
+            self.square_sum = 0.0;
+            self.peak = 0.0;
         }
-
-        Ok(())
-    }
-
-    pub fn get_dr_value(&self) -> Decibel {
-        let loudest_avg = self.loud_parts_rms.iter().sum::<f64>() / self.loud_parts_rms.len() as f64;
-        let quietest_avg = self.quiet_parts_rms.iter().sum::<f64>() / self.quiet_parts_rms.len() as f64;
-
-        Decibel::new(loudest_avg / quietest_avg)
     }
-}
 
-fn get_target_population_count(samples_count: &usize, chunks_size: &usize) -> usize {
-    let chunks_in_signal = samples_count / chunks_size;
-    let target_population_count = (chunks_in_signal * TARGET_SAMPLE_POPULATION_SHARE) / 100;
+    // Unrounded channel value, the track value is the rounded mean over channels
+    #[allow(clippy::cast_precision_loss)]
+    pub fn value(&self) -> Decibel {
+        let full_blocks = self.blocks_rms.len();
+        let blocks = full_blocks + 1;
 
-    target_population_count
-}
+        if blocks < MIN_BLOCKS {
+            return Decibel::new(1.0);
+        }
 
-fn update_quiet_rms_population(new_rms: &f64, quiet_rms_population: &mut Vec<f64>) {
-    let array_size = quiet_rms_population.len();
-    let loudest_element = quiet_rms_population[array_size - 1];
-    
-    if *new_rms > loudest_element {
-        return
-    }
+        // An empty last block stays in the ranking as silence, like in the reference meter
+        let mut rms = self.blocks_rms.clone();
+        let mut peaks = self.blocks_peak.clone();
+        if self.position > 0 {
+            rms.push(block_rms(self.square_sum, self.position));
+            peaks.push(self.peak);
+        } else {
+            rms.push(0.0);
+            peaks.push(0.0);
+        }
 
-    let mut to_insert = *new_rms;
+        rms.sort_by(f64::total_cmp);
+        peaks.sort_by(f64::total_cmp);
 
-    for index in 0..array_size {
-        if to_insert < quiet_rms_population[index] {
-            mem::swap(&mut quiet_rms_population[index], &mut to_insert);
-        }   
-    }
-}
-
-fn update_loud_rms_population(new_rms: &f64, loud_rms_population: &mut Vec<f64>) {
-    let array_size = loud_rms_population.len();
-    let quietest_element = loud_rms_population[array_size - 1];
-    
-    if *new_rms < quietest_element {
-        return
-    }
+        let loudest_count = (blocks / LOUDEST_BLOCKS_DIVISOR).max(1);
+        let loudest_square_sum: f64 = rms[rms.len() - loudest_count..].iter().map(|value| value * value).sum();
+        let loudest_rms = (loudest_square_sum / loudest_count as f64).sqrt();
 
-    let mut to_insert = *new_rms;
+        let second_peak = peaks[full_blocks - 1];
 
-    for index in 0..array_size {
-        if to_insert > loud_rms_population[index] {
-            mem::swap(&mut loud_rms_population[index], &mut to_insert);
+        if loudest_rms == 0.0 || second_peak == 0.0 {
+            return Decibel::new(1.0);
         }
+
+        Decibel::new(second_peak / loudest_rms)
     }
 }
 
-const fn get_chunk_size(sample_rate: Frequency) -> usize {
-    sample_rate.to_hz() * 3
+#[allow(clippy::cast_precision_loss)]
+fn block_rms(square_sum: f64, count: usize) -> f64 {
+    (2.0 * square_sum / count as f64).sqrt()
 }
 
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::cast_precision_loss)]
 mod tests {
-    use rand::Rng;
     use super::*;
 
-    #[test]
-    fn insert_quiet_rms_insert_empty_array() {
-        let mut rng = rand::rng();
-        let mut quiet_array = vec![f64::MAX; 10];
+    const RATE: usize = 100;
 
-        let new_rms: f64 = rng.random_range(0.0..0.9);
-        update_quiet_rms_population(&new_rms, &mut quiet_array);
-
-        assert_eq!(new_rms, quiet_array[0]);
-        assert_eq!(quiet_array.len(), 10);
-        for index in 1..9 {
-            assert_eq!(quiet_array[index], f64::MAX, "Position #{} has changed", index)
+    fn measure(samples: impl IntoIterator<Item = f64>) -> f64 {
+        let mut meter = DynamicRangeMeter::new(Frequency::new(100).unwrap());
+        for sample in samples {
+            meter.push_sample(sample);
         }
+        meter.value().get_value()
     }
 
-    #[test]
-    fn insert_quiet_rms_insert_start() {
-        let mut rng = rand::rng();
-        let mut quiet_array = {
-            let mut res: Vec<f64> = vec![];
-            for _ in 0..10 {
-                res.push(rng.random_range(1.0..2.0))
-            }
-
-            res.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-            res
-        };
-
-        let new_rms: f64 = rng.random_range(0.0..0.9);
-        let expected_new_lst = quiet_array[8];
-        update_quiet_rms_population(&new_rms, &mut quiet_array);
-
-        assert_eq!(new_rms, quiet_array[0]);
-        assert_eq!(quiet_array.len(), 10);
-        assert_eq!(quiet_array[9], expected_new_lst)
+    // A 25 Hz sine at 100 Hz sampling hits its peak on every fourth sample
+    fn sine(amplitude: f64, seconds: usize) -> impl Iterator<Item = f64> {
+        (0..RATE * seconds).map(move |index| amplitude * (std::f64::consts::FRAC_PI_2 * index as f64).sin())
     }
 
     #[test]
-    fn insert_quiet_rms_insert_end() {
-        let mut rng = rand::rng();
-        let mut quiet_array = {
-            let mut res: Vec<f64> = vec![];
-            for _ in 0..10 {
-                res.push(rng.random_range(1.0..2.0))
-            }
-
-            res.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-            res
-        };
-
-        let new_rms: f64 = rng.random_range(quiet_array[8]..quiet_array[9]);
-        let expected_unchanged: Vec<f64> = quiet_array.clone()[0..8].to_vec();
-        update_quiet_rms_population(&new_rms, &mut quiet_array);
-
-        assert_eq!(new_rms, quiet_array[9]);
-        assert_eq!(quiet_array.len(), 10);
-        for index in 0..8 {
-            assert_eq!(quiet_array[index], expected_unchanged[index], "Position #{} has changed", index)
-        }
+    fn steady_sine_has_no_dynamic_range() {
+        assert!(measure(sine(0.5, 30)).abs() < 1e-9);
     }
 
     #[test]
-    fn insert_quiet_rms_insert_middle() {
-        let mut rng = rand::rng();
-        let mut quiet_array = {
-            let mut res: Vec<f64> = vec![];
-            for _ in 0..10 {
-                res.push(rng.random_range(1.0..2.0))
-            }
-
-            res.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-            res
-        };
-
-        let new_rms: f64 = rng.random_range(quiet_array[4]..quiet_array[5]);
-        let expected_unchanged_pre: Vec<f64> = quiet_array.clone()[0..4].to_vec();
-        let expected_unchanged_post: Vec<f64> = quiet_array.clone()[5..8].to_vec();
-        update_quiet_rms_population(&new_rms, &mut quiet_array);
-
-        assert_eq!(new_rms, quiet_array[5]);
-        assert_eq!(quiet_array.len(), 10);
-        for index in 0..4 {
-            assert_eq!(quiet_array[index], expected_unchanged_pre[index], "Position #{} has changed", index)
-        }
-        for index in 6..9 {
-            assert_eq!(quiet_array[index], expected_unchanged_post[index - 6], "Position #{} has changed", index)
-        }
+    fn square_wave_reads_minus_three() {
+        let value = measure((0..RATE * 30).map(|index| if index % 2 == 0 { 0.5 } else { -0.5 }));
+
+        assert!((value + 20.0 * 2f64.sqrt().log10()).abs() < 1e-9);
     }
 
     #[test]
-    fn insert_loud_rms_insert_empty_array() {
-        let mut rng = rand::rng();
-        let mut loud_array = vec![f64::MIN; 10];
+    fn quiet_blocks_are_outside_the_loudest_share() {
+        // 8 quiet blocks, 2 loud ones: only the loud ones are averaged
+        let samples = sine(0.1, 24).chain(sine(0.8, 6));
 
-        let new_rms: f64 = rng.random_range(0.0..0.9);
-        update_loud_rms_population(&new_rms, &mut loud_array);
-
-        assert_eq!(new_rms, loud_array[0]);
-        assert_eq!(loud_array.len(), 10);
-        for index in 1..9 {
-            assert_eq!(loud_array[index], f64::MIN, "Position #{} has changed", index)
-        }
+        assert!(measure(samples).abs() < 1e-9);
     }
 
-        #[test]
-    fn insert_loud_rms_insert_start() {
-        let mut rng = rand::rng();
-        let mut loud_array = {
-            let mut res: Vec<f64> = vec![];
-            for _ in 0..10 {
-                res.push(rng.random_range(1.0..2.0))
-            }
+    #[test]
+    fn single_loud_peak_is_ignored() {
+        let mut samples: Vec<f64> = sine(0.25, 30).collect();
+        samples[1001] = 1.0;
 
-            res.sort_by(|a, b| b.partial_cmp(a).unwrap());
+        // Only the louder block RMS shows, the peak stays the regular one
+        let single = measure(samples.iter().copied());
+        assert!(single < 0.0 && single > -0.5);
 
-            res
-        };
+        samples[2001] = 1.0;
+        assert!(measure(samples.iter().copied()) > 11.0);
+    }
 
-        let new_rms: f64 = rng.random_range(2.0..3.0);
-        let expected_new_lst = loud_array[8];
-        update_loud_rms_population(&new_rms, &mut loud_array);
+    #[test]
+    fn last_partial_block_is_ranked_with_its_own_length() {
+        // 5 full quiet blocks select a single loudest block, which is the 1 second tail
+        let samples = sine(0.1, 15).chain(sine(0.4, 1));
 
-        assert_eq!(new_rms, loud_array[0]);
-        assert_eq!(loud_array.len(), 10);
-        assert_eq!(loud_array[9], expected_new_lst)
+        // Second highest peak is 0.1 against a tail RMS of 0.4
+        assert!((measure(samples) - 20.0 * 0.25f64.log10()).abs() < 1e-9);
     }
 
-        #[test]
-    fn insert_loud_rms_insert_end() {
-        let mut rng = rand::rng();
-        let mut loud_array = {
-            let mut res: Vec<f64> = vec![];
-            for _ in 0..10 {
-                res.push(rng.random_range(1.0..2.0))
-            }
+    #[test]
+    fn short_and_silent_signals() {
+        assert!(measure(sine(0.5, 1)).abs() < 1e-9);
+        assert_eq!(measure(std::iter::repeat_n(0.0, RATE * 6)), 0.0);
+        assert_eq!(measure([]), 0.0);
+    }
 
-            res.sort_by(|a, b| b.partial_cmp(a).unwrap());
+    // Whole-signal port of compute_dr14 from dr14_t.meter, the open reference
+    // implementation of the TT DR meter, for one channel
+    fn reference(samples: &[f64]) -> f64 {
+        let block_samples = RATE * BLOCK_SECONDS;
+        let segments = samples.len() / block_samples + 1;
+        if segments < 3 {
+            return 0.0;
+        }
+        let block_rms = |block: &[f64]| (2.0 * block.iter().map(|sample| sample * sample).sum::<f64>() / block.len() as f64).sqrt();
+        let block_peak = |block: &[f64]| block.iter().fold(0.0, |peak: f64, sample| peak.max(sample.abs()));
+
+        let mut rms = vec![0.0; segments];
+        let mut peaks = vec![0.0; segments];
+        for (index, block) in samples.chunks(block_samples).enumerate() {
+            rms[index] = block_rms(block);
+            peaks[index] = block_peak(block);
+        }
+        rms.sort_by(f64::total_cmp);
+        peaks.sort_by(f64::total_cmp);
 
-            res
-        };
+        let loudest = (segments / 5).max(1);
+        let rms_sum: f64 = rms[segments - loudest..].iter().map(|value| value * value).sum();
 
-        let new_rms: f64 = rng.random_range(loud_array[9]..loud_array[8]);
-        let expected_unchanged: Vec<f64> = loud_array.clone()[0..8].to_vec();
-        update_loud_rms_population(&new_rms, &mut loud_array);
+        20.0 * (peaks[segments - 2] / (rms_sum / loudest as f64).sqrt()).log10()
+    }
 
-        assert_eq!(new_rms, loud_array[9]);
-        assert_eq!(loud_array.len(), 10);
-        for index in 0..8 {
-            assert_eq!(loud_array[index], expected_unchanged[index], "Position #{} has changed", index)
-        }
+    // A level and a crest factor that change from block to block
+    fn program(tenths_of_seconds: usize) -> Vec<f64> {
+        let mut state = 0x2545_f491_u32;
+
+        (0..RATE * tenths_of_seconds / 10)
+            .map(|index| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let noise = f64::from(state) / f64::from(u32::MAX) - 0.5;
+                let level = 0.1 + 0.08 * ((index / (RATE * 3)) % 7) as f64;
+                level * (0.7 * (0.3 * index as f64).sin() + noise)
+            })
+            .collect()
     }
 
-        #[test]
-    fn insert_loud_rms_insert_middle() {
-        let mut rng = rand::rng();
-        let mut loud_array = {
-            let mut res: Vec<f64> = vec![];
-            for _ in 0..10 {
-                res.push(rng.random_range(1.0..2.0))
-            }
-
-            res.sort_by(|a, b| b.partial_cmp(a).unwrap());
-
-            res
-        };
-
-        let new_rms: f64 = rng.random_range(loud_array[5]..loud_array[4]);
-        let expected_unchanged_pre: Vec<f64> = loud_array.clone()[0..4].to_vec();
-        let expected_unchanged_post: Vec<f64> = loud_array.clone()[5..8].to_vec();
-        update_loud_rms_population(&new_rms, &mut loud_array);
-
-        assert_eq!(new_rms, loud_array[5]);
-        assert_eq!(loud_array.len(), 10);
-        for index in 0..4 {
-            assert_eq!(loud_array[index], expected_unchanged_pre[index], "Position #{} has changed", index)
-        }
-        for index in 6..9 {
-            assert_eq!(loud_array[index], expected_unchanged_post[index - 6], "Position #{} has changed", index)
+    #[test]
+    fn matches_the_reference_implementation() {
+        // Full blocks and a partial tail: 9 and 14 full blocks select one more loudest
+        // block when the tail is counted, 1 full block is below the 3 block minimum
+        for tenths_of_seconds in [43, 133, 283, 313, 443] {
+            let samples = program(tenths_of_seconds);
+            let expected = reference(&samples);
+            assert!((measure(samples.iter().copied()) - expected).abs() < 1e-9, "{tenths_of_seconds}: {expected}");
         }
+
+        assert!(reference(&program(313)) > 2.0);
+        assert_eq!(reference(&program(43)), 0.0);
     }
 }
-
@@ -0,0 +1,429 @@
+// Lossy transcode ("fake lossless") detection. MP3 and AAC encoders apply a hard lowpass,
+// commonly at 16, 17, 19 or 20 kHz, that holds for the whole track, and leave the
+// scalefactor bands just below it empty whenever they run short of bits. The mixdown is
+// cut in 250 Hz bands frame by frame and every frame is summarised against each band
+// where a lowpass may sit, then the steepest cliff of the long-term average picks the
+// cutoff and its summary. Memory does not grow with the length of the file
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
+
+use crate::model::{
+    frequency::Frequency,
+    lossy_transcode::{LossyTranscode, TranscodeVerdict},
+    sonicprobe_error::SonicProbeError,
+    spectrum::SpectrumWindow,
+};
+
+const FRAME_SIZE: usize = 4096;
+const BAND_WIDTH: f64 = 250.0;
+const MIN_CUTOFF: f64 = 14_000.0;
+const MAX_CUTOFF: f64 = 20_500.0;
+const TYPICAL_CUTOFFS: [f64; 4] = [16_000.0, 17_000.0, 19_000.0, 20_000.0];
+const TYPICAL_CUTOFF_TOLERANCE: f64 = 400.0;
+// Bands averaged on each side of the cliff, the band right above it is the transition
+const SIDE_BANDS: usize = 4;
+const HOLE_REGION_BANDS: usize = 16;
+const LOUD_FRAME: f64 = -60.0;
+const MIN_FRAMES: usize = 16;
+const CLIFF_DROP: f64 = 25.0;
+const FULL_CLIFF_DROP: f64 = 50.0;
+const FRAME_DROP: f64 = 15.0;
+const BREAKTHROUGH: f64 = 10.0;
+const HOLE_MARGIN: f64 = 6.0;
+const ACTIVE_MARGIN: f64 = 20.0;
+const FULL_HOLES: f64 = 0.05;
+const TRANSCODED_SCORE: f64 = 0.6;
+const SUSPICIOUS_SCORE: f64 = 0.35;
+const SILENT_BAND: f64 = -200.0;
+// Below this the content under a cliff is quantisation noise or leakage, not music
+const MIN_CONTENT: f64 = -120.0;
+// Levels above the cutoff of the frames without a cliff, in half dB steps from silence to +20 dBFS
+const HISTOGRAM_STEP: f64 = 0.5;
+const HISTOGRAM_BINS: usize = 440;
+
+// What the frames tell about a cliff right above one band: how many keep it, the holes
+// they leave below it and how loud the frames that lose it are above it
+struct CandidateFrames {
+    supporting: usize,
+    holes: usize,
+    cells: usize,
+    above_levels: Vec<usize>,
+}
+
+impl CandidateFrames {
+    fn new() -> Self {
+        Self { supporting: 0, holes: 0, cells: 0, above_levels: vec![0; HISTOGRAM_BINS] }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    fn push_frame(&mut self, levels: &[f64], band: usize) {
+        let frame_above = side_level(levels, band + 2);
+
+        if cliff_drop(levels, band) >= FRAME_DROP {
+            self.supporting += 1;
+            let region = &levels[(band + 1).saturating_sub(HOLE_REGION_BANDS)..=band];
+            if let Some(frame_holes) = frame_holes(region, frame_above) {
+                self.holes += frame_holes;
+                self.cells += region.len();
+            }
+        } else {
+            let bin = ((frame_above - SILENT_BAND) / HISTOGRAM_STEP).max(0.0) as usize;
+            self.above_levels[bin.min(HISTOGRAM_BINS - 1)] += 1;
+        }
+    }
+
+    // Frames without the cliff that break through it, to within half a dB
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    fn contradicting(&self, above: f64) -> usize {
+        let first = ((above + BREAKTHROUGH - SILENT_BAND) / HISTOGRAM_STEP).max(0.0) as usize;
+        self.above_levels.iter().skip(first.min(HISTOGRAM_BINS - 1)).sum()
+    }
+}
+
+pub struct LossyTranscodeMeter {
+    sample_rate: f64,
+    fft: Arc<dyn RealToComplex<f64>>,
+    window: Vec<f64>,
+    scale: f64,
+    frame: Vec<f64>,
+    input: Vec<f64>,
+    output: Vec<Complex<f64>>,
+    scratch: Vec<Complex<f64>>,
+    levels: Vec<f64>,
+    // Power of every band summed over the loud frames
+    band_powers: Vec<f64>,
+    frames: usize,
+    candidate_bands: Range<usize>,
+    candidates: Vec<CandidateFrames>,
+}
+
+impl LossyTranscodeMeter {
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    #[must_use]
+    pub fn new(sample_rate: Frequency) -> Self {
+        let fft = RealFftPlanner::<f64>::new().plan_fft_forward(FRAME_SIZE);
+        let window = SpectrumWindow::Hann.coefficients(FRAME_SIZE);
+        let coherent_gain: f64 = window.iter().sum();
+        let sample_rate = f64::from(sample_rate);
+        let band_count = (sample_rate / 2.0 / BAND_WIDTH) as usize;
+        let candidate_bands = candidate_bands(band_count);
+
+        Self {
+            sample_rate,
+            input: fft.make_input_vec(),
+            output: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            fft,
+            scale: (2.0 / coherent_gain).powi(2),
+            window,
+            frame: Vec::with_capacity(FRAME_SIZE),
+            levels: vec![0.0; band_count],
+            band_powers: vec![0.0; band_count],
+            frames: 0,
+            candidates: candidate_bands.clone().map(|_| CandidateFrames::new()).collect(),
+            candidate_bands,
+        }
+    }
+
+    /// Samples of the mixdown of all channels, the lowpass of a lossy codec applies to all of them
+    ///
+    /// # Errors
+    ///
+    /// Fails when the FFT rejects a frame, which only happens if its buffers no longer match the
+    /// planned size.
+    pub fn push_samples(&mut self, samples: &[f64]) -> Result<(), SonicProbeError> {
+        let mut remaining = samples;
+
+        while !remaining.is_empty() {
+            let taken = remaining.len().min(FRAME_SIZE - self.frame.len());
+            self.frame.extend_from_slice(&remaining[..taken]);
+            remaining = &remaining[taken..];
+
+            if self.frame.len() == FRAME_SIZE {
+                self.process_frame()?;
+                self.frame.clear();
+            }
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    fn process_frame(&mut self) -> Result<(), SonicProbeError> {
+        let mean_square = self.frame.iter().map(|sample| sample * sample).sum::<f64>() / FRAME_SIZE as f64;
+        if 10.0 * mean_square.log10() < LOUD_FRAME {
+            return Ok(());
+        }
+
+        for ((input, sample), weight) in self.input.iter_mut().zip(&self.frame).zip(&self.window) {
+            *input = sample * weight;
+        }
+
+        self.fft.process_with_scratch(&mut self.input, &mut self.output, &mut self.scratch).map_err(|error| SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: format!("FFT failed: {error}"),
+        })?;
+
+        let bin_width = self.sample_rate / FRAME_SIZE as f64;
+        let mut powers = vec![(0.0, 0usize); self.levels.len()];
+
+        for (bin, value) in self.output.iter().enumerate().skip(1) {
+            let band = (bin as f64 * bin_width / BAND_WIDTH) as usize;
+            if let Some((power, count)) = powers.get_mut(band) {
+                *power += value.norm_sqr() * self.scale;
+                *count += 1;
+            }
+        }
+
+        for ((level, band_power), &(power, count)) in self.levels.iter_mut().zip(&mut self.band_powers).zip(&powers) {
+            *level = if power > 0.0 { 10.0 * (power / count as f64).log10() } else { SILENT_BAND };
+            *band_power += 10f64.powf(*level / 10.0);
+        }
+        self.frames += 1;
+
+        for (candidate, band) in self.candidates.iter_mut().zip(self.candidate_bands.clone()) {
+            candidate.push_frame(&self.levels, band);
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn value(&self) -> LossyTranscode {
+        if self.frames < MIN_FRAMES {
+            return LossyTranscode::INCONCLUSIVE;
+        }
+
+        let average: Vec<f64> =
+            self.band_powers.iter().map(|power| 10.0 * (power / self.frames as f64).log10()).collect();
+
+        let Some((band, drop)) = steepest_cliff(&average) else {
+            return LossyTranscode::INCONCLUSIVE;
+        };
+
+        if drop < CLIFF_DROP {
+            return verdict(0.3 * (drop / CLIFF_DROP).max(0.0), None, 0.0, 0.0);
+        }
+
+        let Some(candidate) = self.candidates.get(band - self.candidate_bands.start) else {
+            return LossyTranscode::INCONCLUSIVE;
+        };
+
+        let supporting = candidate.supporting;
+        let contradicting = candidate.contradicting(side_level(&average, band + 2));
+
+        let persistence = if supporting == 0 { 0.0 } else { supporting as f64 / (supporting + contradicting) as f64 };
+        let holes = if candidate.cells == 0 { 0.0 } else { candidate.holes as f64 / candidate.cells as f64 };
+        let cutoff = (band + 1) as f64 * BAND_WIDTH;
+
+        let mut score =
+            0.5 * persistence + 0.3 * (drop / FULL_CLIFF_DROP).min(1.0) + 0.2 * (holes / FULL_HOLES).min(1.0);
+        if !TYPICAL_CUTOFFS.iter().any(|typical| (cutoff - typical).abs() <= TYPICAL_CUTOFF_TOLERANCE) {
+            score *= 0.8;
+        }
+
+        verdict(score, Some(cutoff), persistence, holes)
+    }
+}
+
+fn verdict(score: f64, cutoff: Option<f64>, persistence: f64, holes: f64) -> LossyTranscode {
+    let (verdict, confidence) = if score >= TRANSCODED_SCORE {
+        (TranscodeVerdict::Transcoded, score)
+    } else if score >= SUSPICIOUS_SCORE {
+        (TranscodeVerdict::Suspicious, score)
+    } else {
+        (TranscodeVerdict::Lossless, 1.0 - score)
+    };
+
+    LossyTranscode { verdict, confidence, cutoff, persistence, holes }
+}
+
+// Bands that may sit right below a lossy lowpass: the cutoff above them is one of the
+// usual lowpass frequencies and there are enough bands on both sides to compare
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn candidate_bands(band_count: usize) -> Range<usize> {
+    let first = ((MIN_CUTOFF / BAND_WIDTH).ceil() as usize - 1).max(SIDE_BANDS - 1);
+    let end = ((MAX_CUTOFF / BAND_WIDTH) as usize).min(band_count.saturating_sub(SIDE_BANDS + 1));
+
+    first..end.max(first)
+}
+
+// Last band below the cutoff and the drop in dB across it, searched among the candidate
+// bands where there is content below the cliff, the highest band wins a tie. None when
+// there is nothing to judge, from the sample rate or the program
+fn steepest_cliff(levels: &[f64]) -> Option<(usize, f64)> {
+    candidate_bands(levels.len())
+        .filter(|&band| side_level(levels, band + 1 - SIDE_BANDS) >= MIN_CONTENT)
+        .map(|band| (band, cliff_drop(levels, band)))
+        .fold(None, |best: Option<(usize, f64)>, (band, drop)| match best {
+            Some((_, best_drop)) if best_drop > drop => best,
+            _ => Some((band, drop)),
+        })
+}
+
+fn cliff_drop(levels: &[f64], band: usize) -> f64 {
+    side_level(levels, band + 1 - SIDE_BANDS) - side_level(levels, band + 2)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn side_level(levels: &[f64], first: usize) -> f64 {
+    let side = &levels[first..(first + SIDE_BANDS).min(levels.len())];
+    side.iter().sum::<f64>() / side.len() as f64
+}
+
+// Bands as empty as the region above the cutoff while the rest of the region carries
+// music, None when too little of the region is active to tell holes from quiet content
+fn frame_holes(region: &[f64], floor: f64) -> Option<usize> {
+    let active = region.iter().filter(|&&level| level >= floor + ACTIVE_MARGIN).count();
+    if active * 2 < region.len() {
+        return None;
+    }
+
+    Some(region.iter().filter(|&&level| level <= floor + HOLE_MARGIN).count())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::cast_precision_loss)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    const SECONDS: usize = 6;
+
+    // Deterministic white noise, xorshift64
+    fn noise(length: usize) -> Vec<f64> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect()
+    }
+
+    // Blackman windowed sinc, about 75 dB of stopband rejection
+    fn lowpass(samples: &[f64], cutoff: f64, rate: f64) -> Vec<f64> {
+        let taps = 511;
+        let middle = (taps / 2) as f64;
+        let kernel: Vec<f64> = (0..taps)
+            .map(|tap| {
+                let offset = tap as f64 - middle;
+                let sinc = if offset == 0.0 { 2.0 * cutoff / rate } else { (2.0 * PI * cutoff / rate * offset).sin() / (PI * offset) };
+                let phase = 2.0 * PI * tap as f64 / (taps - 1) as f64;
+                sinc * (0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos())
+            })
+            .collect();
+
+        samples.windows(taps).map(|window| window.iter().zip(&kernel).map(|(sample, weight)| sample * weight).sum()).collect()
+    }
+
+    fn measure(samples: &[f64]) -> LossyTranscode {
+        let mut meter = LossyTranscodeMeter::new(Frequency::CD_QUALITY);
+        // Uneven blocks, as the decoders deliver them
+        for block in samples.chunks(1000) {
+            meter.push_samples(block).unwrap();
+        }
+        meter.value()
+    }
+
+    #[test]
+    fn full_band_noise_is_lossless() {
+        let result = measure(&noise(44100 * SECONDS));
+
+        assert_eq!(result.verdict, TranscodeVerdict::Lossless);
+        assert!(result.confidence > 0.9);
+        assert_eq!(result.cutoff, None);
+    }
+
+    #[test]
+    fn hard_lowpass_is_detected() {
+        let result = measure(&lowpass(&noise(44100 * SECONDS), 16_000.0, 44100.0));
+
+        assert_eq!(result.verdict, TranscodeVerdict::Transcoded);
+        assert!((result.cutoff.unwrap() - 16_000.0).abs() <= 500.0, "{:?}", result.cutoff);
+        assert_eq!(result.persistence, 1.0);
+        assert!(result.confidence >= 0.75);
+    }
+
+    #[test]
+    fn gentle_rolloff_is_not_a_cutoff() {
+        // One pole lowpass at 2 kHz, 6 dB per octave as in natural recordings
+        let coefficient = (-2.0 * PI * 2000.0 / 44100.0).exp();
+        let mut state = 0.0;
+        let samples: Vec<f64> = noise(44100 * SECONDS)
+            .into_iter()
+            .map(|sample| {
+                state = sample * (1.0 - coefficient) + state * coefficient;
+                state
+            })
+            .collect();
+
+        assert_eq!(measure(&samples).verdict, TranscodeVerdict::Lossless);
+    }
+
+    #[test]
+    fn silence_is_inconclusive() {
+        let result = measure(&vec![0.0; 44100 * SECONDS]);
+
+        assert_eq!(result.verdict, TranscodeVerdict::Inconclusive);
+        assert_eq!(result.confidence, 0.0);
+    }
+
+    #[test]
+    fn holes_need_an_active_region() {
+        let floor = -120.0;
+
+        assert_eq!(frame_holes(&[-60.0, -118.0, -62.0, -61.0], floor), Some(1));
+        assert_eq!(frame_holes(&[-60.0, -118.0, -119.0, -117.0], floor), None);
+    }
+
+    #[test]
+    fn candidates_do_not_depend_on_the_length() {
+        // 14 to 20.5 kHz whatever the sample rate, each with a fixed size summary
+        assert_eq!(candidate_bands(88), 55..82);
+        assert_eq!(candidate_bands(384), 55..82);
+        assert_eq!(candidate_bands(64), 55..59);
+        assert!(candidate_bands(40).is_empty());
+
+        let mut candidate = CandidateFrames::new();
+        let mut levels = vec![-60.0; 88];
+        for above in [-80.0, -69.0, -64.0, -50.0] {
+            levels[65..].fill(above);
+            candidate.push_frame(&levels, 63);
+        }
+
+        // Only the -80 dB frame keeps a cliff, two of the others rise 10 dB above -78 dB
+        assert_eq!(candidate.supporting, 1);
+        assert_eq!(candidate.contradicting(-78.0), 2);
+        assert_eq!(candidate.above_levels.iter().sum::<usize>(), 3);
+    }
+
+    #[test]
+    fn cliff_is_searched_in_the_lowpass_range() {
+        let mut levels = vec![-60.0; 88];
+        levels[64..].fill(-140.0);
+
+        assert_eq!(steepest_cliff(&levels), Some((63, 80.0)));
+        assert_eq!(steepest_cliff(&levels[..40]), None);
+
+        // A cliff in the leakage of a test tone says nothing about the source
+        levels.fill(-180.0);
+        levels[78..].fill(-230.0);
+        assert_eq!(steepest_cliff(&levels), None);
+    }
+}
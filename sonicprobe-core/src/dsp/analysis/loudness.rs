@@ -0,0 +1,532 @@
+// ITU-R BS.1770-4 loudness: K-weighting, 400 ms blocks with 75% overlap,
+// absolute gate at -70 LUFS and relative gate 10 LU below the ungated level
+
+use std::f64::consts::PI;
+
+use crate::model::{
+    channel_layout::ChannelLabel,
+    decibel::Decibel,
+    frequency::Frequency,
+    loudness::{Loudness, LoudnessPoint},
+};
+
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+const STEPS_PER_BLOCK: usize = 4;
+const STEPS_PER_SHORT_TERM_WINDOW: usize = 30;
+const RANGE_RELATIVE_GATE: f64 = -20.0;
+const RANGE_LOW_PERCENTILE: f64 = 0.10;
+const RANGE_HIGH_PERCENTILE: f64 = 0.95;
+
+#[derive(Clone, Copy)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    const fn new(b0: f64, b1: f64, b2: f64, a1: f64, a2: f64) -> Self {
+        Self { b0, b1, b2, a1, a2, z1: 0.0, z2: 0.0 }
+    }
+
+    #[inline]
+    fn process(&mut self, sample: f64) -> f64 {
+        let output = self.b0.mul_add(sample, self.z1);
+        self.z1 = self.b1.mul_add(sample, -self.a1 * output) + self.z2;
+        self.z2 = self.b2.mul_add(sample, -self.a2 * output);
+        output
+    }
+}
+
+// Analogue prototypes of the BS.1770 filters, bilinear transformed for the actual rate
+// so that they match the published 48 kHz coefficients and work at any other rate
+#[derive(Clone, Copy)]
+pub struct KWeightingFilter {
+    pre_filter: Biquad,
+    rlb_filter: Biquad,
+}
+
+impl KWeightingFilter {
+    #[must_use]
+    pub fn new(sample_rate: Frequency) -> Self {
+        let rate = f64::from(sample_rate);
+
+        let gain_db = 3.999_843_853_973_347;
+        let quality = 0.707_175_236_955_419_6;
+        let k = (PI * 1_681.974_450_955_533 / rate).tan();
+        let high_gain = 10f64.powf(gain_db / 20.0);
+        let band_gain = high_gain.powf(0.499_666_774_154_541_6);
+        let a0 = 1.0 + k / quality + k * k;
+        let pre_filter = Biquad::new(
+            (high_gain + band_gain * k / quality + k * k) / a0,
+            2.0 * (k * k - high_gain) / a0,
+            (high_gain - band_gain * k / quality + k * k) / a0,
+            2.0 * (k * k - 1.0) / a0,
+            (1.0 - k / quality + k * k) / a0,
+        );
+
+        let quality = 0.500_327_037_323_877_3;
+        let k = (PI * 38.135_470_876_024_44 / rate).tan();
+        let a0 = 1.0 + k / quality + k * k;
+        let rlb_filter = Biquad::new(1.0, -2.0, 1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / quality + k * k) / a0);
+
+        Self { pre_filter, rlb_filter }
+    }
+
+    #[inline]
+    pub fn process(&mut self, sample: f64) -> f64 {
+        self.rlb_filter.process(self.pre_filter.process(sample))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ChannelStep {
+    energy: f64,
+    peak: f64,
+    true_peak: f64,
+}
+
+// Energy of one K-weighted channel summed over consecutive 100 ms steps, the building
+// blocks of the gating blocks and short-term windows, along with the peaks of each step
+pub struct ChannelStepMeter {
+    filter: KWeightingFilter,
+    samples_per_step: usize,
+    position: usize,
+    current: ChannelStep,
+    steps: Vec<ChannelStep>,
+}
+
+impl ChannelStepMeter {
+    #[must_use]
+    pub fn new(sample_rate: Frequency) -> Self {
+        Self {
+            filter: KWeightingFilter::new(sample_rate),
+            samples_per_step: samples_per_step(sample_rate),
+            position: 0,
+            current: ChannelStep::default(),
+            steps: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn push_sample(&mut self, sample: f64, true_peak: f64) {
+        self.current.energy += self.filter.process(sample).powi(2);
+        self.current.peak = self.current.peak.max(sample.abs());
+        self.current.true_peak = self.current.true_peak.max(true_peak);
+        self.position += 1;
+
+        if self.position == self.samples_per_step {
+            self.steps.push(self.current);
+            self.current = ChannelStep::default();
+            self.position = 0;
+        }
+    }
+}
+
+pub struct LoudnessMeter {
+    samples_per_step: usize,
+    weighted_steps: Vec<f64>,
+    peaks: Vec<f64>,
+    true_peaks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    #[must_use]
+    pub fn new(sample_rate: Frequency) -> Self {
+        Self {
+            samples_per_step: samples_per_step(sample_rate),
+            weighted_steps: Vec::new(),
+            peaks: Vec::new(),
+            true_peaks: Vec::new(),
+        }
+    }
+
+    pub fn add_channel(&mut self, label: ChannelLabel, channel: &ChannelStepMeter) {
+        let weight = channel_weight(label);
+        let steps = &channel.steps;
+
+        if self.weighted_steps.len() < steps.len() {
+            self.weighted_steps.resize(steps.len(), 0.0);
+            self.peaks.resize(steps.len(), 0.0);
+            self.true_peaks.resize(steps.len(), 0.0);
+        }
+
+        for (index, step) in steps.iter().enumerate() {
+            self.weighted_steps[index] += weight * step.energy;
+            self.peaks[index] = self.peaks[index].max(step.peak);
+            self.true_peaks[index] = self.true_peaks[index].max(step.true_peak);
+        }
+    }
+
+    // One point every 100 ms once the first momentary block is complete, peaks are the
+    // highest across all channels over the same 400 ms and 3 s windows as the loudness
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn series(&self) -> Vec<LoudnessPoint> {
+        let momentary = self.window_powers(STEPS_PER_BLOCK);
+        let momentary_peaks = window_peaks(&self.peaks, STEPS_PER_BLOCK);
+        let momentary_true_peaks = window_peaks(&self.true_peaks, STEPS_PER_BLOCK);
+        let short_term = self.window_powers(STEPS_PER_SHORT_TERM_WINDOW);
+        let short_term_peaks = window_peaks(&self.peaks, STEPS_PER_SHORT_TERM_WINDOW);
+        let short_term_true_peaks = window_peaks(&self.true_peaks, STEPS_PER_SHORT_TERM_WINDOW);
+        let psr = self.psr();
+
+        momentary
+            .into_iter()
+            .enumerate()
+            .map(|(block, power)| {
+                let step = block + STEPS_PER_BLOCK - 1;
+                let window = step.checked_sub(STEPS_PER_SHORT_TERM_WINDOW - 1);
+
+                LoudnessPoint {
+                    time: (step + 1) as f64 / 10.0,
+                    momentary: Loudness::new(loudness(power)),
+                    peak: Decibel::new(momentary_peaks[block]),
+                    true_peak: Decibel::new(momentary_true_peaks[block]),
+                    short_term: window.map(|window| Loudness::new(loudness(short_term[window]))),
+                    short_term_peak: window.map(|window| Decibel::new(short_term_peaks[window])),
+                    short_term_true_peak: window.map(|window| Decibel::new(short_term_true_peaks[window])),
+                    psr: window.map(|window| psr[window]),
+                }
+            })
+            .collect()
+    }
+
+    // Mean square of every complete window of `steps` steps, one window every 100 ms
+    #[allow(clippy::cast_precision_loss)]
+    fn window_powers(&self, steps: usize) -> Vec<f64> {
+        let window_samples = (steps * self.samples_per_step) as f64;
+
+        self.weighted_steps
+            .windows(steps)
+            .map(|window| window.iter().sum::<f64>() / window_samples)
+            .collect()
+    }
+
+    // Peak to short-term loudness ratio: true peak of every 3 s window minus its loudness
+    #[must_use]
+    pub fn psr(&self) -> Vec<f64> {
+        self.window_powers(STEPS_PER_SHORT_TERM_WINDOW)
+            .into_iter()
+            .zip(window_peaks(&self.true_peaks, STEPS_PER_SHORT_TERM_WINDOW))
+            .map(|(power, true_peak)| Decibel::new(true_peak).get_value() - loudness(power))
+            .collect()
+    }
+
+    // Mean square of every 400 ms gating block, kept so that several programs can be gated together
+    #[must_use]
+    pub fn blocks(&self) -> Vec<f64> {
+        self.window_powers(STEPS_PER_BLOCK)
+    }
+
+    #[must_use]
+    pub fn integrated_loudness(&self) -> f64 {
+        gated_loudness(&self.blocks())
+    }
+
+    // Loudest 3 s window, `f64::NEG_INFINITY` for programs shorter than one window
+    pub fn max_short_term_loudness(&self) -> f64 {
+        self.window_powers(STEPS_PER_SHORT_TERM_WINDOW).into_iter().map(loudness).fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    // EBU Tech 3342: spread between the 10th and 95th percentile of the gated 3 s short-term loudness
+    #[allow(clippy::cast_sign_loss)]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn loudness_range(&self) -> f64 {
+        let absolute_gated: Vec<f64> = self
+            .window_powers(STEPS_PER_SHORT_TERM_WINDOW)
+            .into_iter()
+            .filter(|power| loudness(*power) > ABSOLUTE_GATE)
+            .collect();
+
+        if absolute_gated.is_empty() {
+            return 0.0;
+        }
+
+        let relative_gate = loudness(mean(&absolute_gated)) + RANGE_RELATIVE_GATE;
+        let mut gated: Vec<f64> =
+            absolute_gated.into_iter().map(loudness).filter(|level| *level > relative_gate).collect();
+        gated.sort_by(f64::total_cmp);
+
+        let percentile = |fraction: f64| gated[((gated.len() - 1) as f64 * fraction).round() as usize];
+
+        percentile(RANGE_HIGH_PERCENTILE) - percentile(RANGE_LOW_PERCENTILE)
+    }
+}
+
+// Highest step peak of every complete window of `steps` steps, aligned with `window_powers`
+fn window_peaks(peaks: &[f64], steps: usize) -> Vec<f64> {
+    peaks.windows(steps).map(|window| window.iter().copied().fold(0.0, f64::max)).collect()
+}
+
+// `f64::NEG_INFINITY` when no block is above the absolute gate
+#[must_use]
+pub fn gated_loudness(blocks: &[f64]) -> f64 {
+    let absolute_gated: Vec<f64> = blocks.iter().copied().filter(|power| loudness(*power) > ABSOLUTE_GATE).collect();
+
+    if absolute_gated.is_empty() {
+        return f64::NEG_INFINITY;
+    }
+
+    let relative_gate = loudness(mean(&absolute_gated)) + RELATIVE_GATE;
+    let relative_gated: Vec<f64> =
+        absolute_gated.into_iter().filter(|power| loudness(*power) > relative_gate).collect();
+
+    loudness(mean(&relative_gated))
+}
+
+// Surround channels get +1.5 dB, the LFE channel is left out of the measurement
+#[must_use]
+pub const fn channel_weight(label: ChannelLabel) -> f64 {
+    match label {
+        ChannelLabel::LowFrequency => 0.0,
+        ChannelLabel::SideLeft | ChannelLabel::SideRight | ChannelLabel::BackLeft | ChannelLabel::BackRight => 1.41,
+        _ => 1.0,
+    }
+}
+
+const fn samples_per_step(sample_rate: Frequency) -> usize {
+    (sample_rate.to_hz() + 5) / 10
+}
+
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    // EBU Tech 3341 and 3342 signals are 1 kHz sines with the given peak level in dBFS
+    fn tone(sample_rate: Frequency, segments: &[(f64, f64)]) -> ChannelStepMeter {
+        let rate = f64::from(sample_rate);
+        let mut channel = ChannelStepMeter::new(sample_rate);
+        let mut index = 0.0;
+
+        for &(level, seconds) in segments {
+            let amplitude = 10f64.powf(level / 20.0);
+            let end = index + (seconds * rate).round();
+
+            while index < end {
+                let sample = amplitude * (2.0 * PI * 1000.0 * index / rate).sin();
+                channel.push_sample(sample, sample.abs());
+                index += 1.0;
+            }
+        }
+
+        channel
+    }
+
+    fn measure(sample_rate: u32, channels: &[(ChannelLabel, &[(f64, f64)])]) -> f64 {
+        let sample_rate = Frequency::new(sample_rate).unwrap();
+        let mut meter = LoudnessMeter::new(sample_rate);
+
+        for (label, segments) in channels {
+            meter.add_channel(*label, &tone(sample_rate, segments));
+        }
+
+        meter.integrated_loudness()
+    }
+
+    fn stereo(sample_rate: u32, segments: &[(f64, f64)]) -> f64 {
+        measure(sample_rate, &[(ChannelLabel::FrontLeft, segments), (ChannelLabel::FrontRight, segments)])
+    }
+
+    fn assert_loudness(measured: f64, expected: f64) {
+        assert!((measured - expected).abs() <= 0.1, "measured {measured} LUFS, expected {expected} LUFS");
+    }
+
+    #[test]
+    fn tech_3341_case_1() {
+        assert_loudness(stereo(48000, &[(-23.0, 20.0)]), -23.0);
+    }
+
+    #[test]
+    fn tech_3341_case_2() {
+        assert_loudness(stereo(48000, &[(-33.0, 20.0)]), -33.0);
+    }
+
+    #[test]
+    fn tech_3341_case_3() {
+        assert_loudness(stereo(48000, &[(-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0)]), -23.0);
+    }
+
+    #[test]
+    fn tech_3341_case_4() {
+        let segments = [(-72.0, 10.0), (-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0), (-72.0, 10.0)];
+        assert_loudness(stereo(48000, &segments), -23.0);
+    }
+
+    #[test]
+    fn tech_3341_case_5() {
+        assert_loudness(stereo(48000, &[(-26.0, 20.0), (-20.0, 20.1), (-26.0, 20.0)]), -23.0);
+    }
+
+    #[test]
+    fn tech_3341_case_6() {
+        let measured = measure(
+            48000,
+            &[
+                (ChannelLabel::FrontLeft, &[(-28.0, 20.0)]),
+                (ChannelLabel::FrontRight, &[(-28.0, 20.0)]),
+                (ChannelLabel::FrontCenter, &[(-24.0, 20.0)]),
+                (ChannelLabel::SideLeft, &[(-30.0, 20.0)]),
+                (ChannelLabel::SideRight, &[(-30.0, 20.0)]),
+            ],
+        );
+
+        assert_loudness(measured, -23.0);
+    }
+
+    fn stereo_range(segments: &[(f64, f64)]) -> f64 {
+        let mut meter = LoudnessMeter::new(Frequency::PRO_AUDIO);
+        let channel = tone(Frequency::PRO_AUDIO, segments);
+
+        meter.add_channel(ChannelLabel::FrontLeft, &channel);
+        meter.add_channel(ChannelLabel::FrontRight, &channel);
+        meter.loudness_range()
+    }
+
+    fn assert_range(measured: f64, expected: f64) {
+        assert!((measured - expected).abs() <= 1.0, "measured {measured} LU, expected {expected} LU");
+    }
+
+    #[test]
+    fn tech_3342_case_1() {
+        assert_range(stereo_range(&[(-20.0, 20.0), (-30.0, 20.0)]), 10.0);
+    }
+
+    #[test]
+    fn tech_3342_case_2() {
+        assert_range(stereo_range(&[(-20.0, 20.0), (-15.0, 20.0)]), 5.0);
+    }
+
+    #[test]
+    fn tech_3342_case_3() {
+        assert_range(stereo_range(&[(-40.0, 20.0), (-20.0, 20.0)]), 20.0);
+    }
+
+    #[test]
+    fn tech_3342_case_4() {
+        let segments = [(-50.0, 20.0), (-35.0, 20.0), (-20.0, 20.0), (-35.0, 20.0), (-50.0, 20.0)];
+        assert_range(stereo_range(&segments), 15.0);
+    }
+
+    #[test]
+    fn steady_tone_has_no_range() {
+        assert!(stereo_range(&[(-23.0, 10.0)]).abs() < 0.1);
+        assert_range(stereo_range(&[(-90.0, 10.0)]), 0.0);
+    }
+
+    #[test]
+    fn series_follows_level_changes() {
+        let mut meter = LoudnessMeter::new(Frequency::PRO_AUDIO);
+        let channel = tone(Frequency::PRO_AUDIO, &[(-23.0, 4.0), (-13.0, 4.0)]);
+        meter.add_channel(ChannelLabel::FrontLeft, &channel);
+        meter.add_channel(ChannelLabel::FrontRight, &channel);
+
+        let series = meter.series();
+
+        assert_eq!(series.len(), 77);
+        assert!((series[0].time - 0.4).abs() < 1e-9);
+        assert!(series[0].short_term.is_none());
+        assert!((series[26].short_term.unwrap().get_value() + 23.0).abs() < 0.1);
+        assert!((series[20].momentary.get_value() + 23.0).abs() < 0.1);
+        assert!((series[60].momentary.get_value() + 13.0).abs() < 0.1);
+        assert!((series[60].peak.get_value() + 13.0).abs() < 0.01);
+        assert!((series[60].short_term_peak.unwrap().get_value() + 13.0).abs() < 0.01);
+        assert!((meter.max_short_term_loudness() + 13.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn psr_follows_the_window_peak() {
+        let mut meter = LoudnessMeter::new(Frequency::PRO_AUDIO);
+        let channel = tone(Frequency::PRO_AUDIO, &[(-23.0, 4.0), (-13.0, 4.0)]);
+        meter.add_channel(ChannelLabel::FrontLeft, &channel);
+        meter.add_channel(ChannelLabel::FrontRight, &channel);
+
+        let psr = meter.psr();
+        let series = meter.series();
+
+        // A sine pair peaking at -23 dBFS measures -23 LUFS, its crest factor and the stereo sum cancel out
+        assert_eq!(psr.len(), 51);
+        assert!(psr[0].abs() < 0.1);
+        assert!(psr[50].abs() < 0.1);
+        // Windows straddling the change hold the loud peak against a quieter level
+        assert!((psr[20] - 3.98).abs() < 0.1);
+        assert!(series[0].psr.is_none());
+        assert_eq!(series[26].psr.unwrap(), psr[0]);
+    }
+
+    #[test]
+    fn series_peaks_cover_their_window() {
+        let mut meter = LoudnessMeter::new(Frequency::PRO_AUDIO);
+        meter.add_channel(ChannelLabel::Mono, &tone(Frequency::PRO_AUDIO, &[(-13.0, 0.1), (-23.0, 4.0)]));
+
+        let series = meter.series();
+
+        // The loud first step stays in the 400 ms window up to 0.4 s and in the 3 s window up to 3 s
+        assert!((series[0].peak.get_value() + 13.0).abs() < 0.01);
+        assert!((series[0].true_peak.get_value() + 13.0).abs() < 0.1);
+        assert!((series[1].peak.get_value() + 23.0).abs() < 0.01);
+        assert!((series[26].short_term_peak.unwrap().get_value() + 13.0).abs() < 0.01);
+        assert!((series[26].short_term_true_peak.unwrap().get_value() + 13.0).abs() < 0.1);
+        assert!((series[27].short_term_peak.unwrap().get_value() + 23.0).abs() < 0.01);
+        assert!(series[25].short_term_peak.is_none());
+    }
+
+    #[test]
+    fn pooled_blocks_gate_as_one_program() {
+        let mut quiet = LoudnessMeter::new(Frequency::PRO_AUDIO);
+        quiet.add_channel(ChannelLabel::Mono, &tone(Frequency::PRO_AUDIO, &[(-30.0, 10.0)]));
+        let mut loud = LoudnessMeter::new(Frequency::PRO_AUDIO);
+        loud.add_channel(ChannelLabel::Mono, &tone(Frequency::PRO_AUDIO, &[(-20.0, 10.0)]));
+
+        let mut concatenated = LoudnessMeter::new(Frequency::PRO_AUDIO);
+        concatenated.add_channel(ChannelLabel::Mono, &tone(Frequency::PRO_AUDIO, &[(-30.0, 10.0), (-20.0, 10.0)]));
+
+        let pooled: Vec<f64> = quiet.blocks().into_iter().chain(loud.blocks()).collect();
+
+        assert!((gated_loudness(&pooled) - concatenated.integrated_loudness()).abs() < 0.05);
+    }
+
+    #[test]
+    fn filters_follow_the_sample_rate() {
+        assert_loudness(stereo(44100, &[(-23.0, 20.0)]), -23.0);
+        assert_loudness(stereo(96000, &[(-23.0, 20.0)]), -23.0);
+        assert_loudness(stereo(192_000, &[(-23.0, 10.0)]), -23.0);
+    }
+
+    #[test]
+    fn lfe_is_ignored() {
+        let measured = measure(
+            48000,
+            &[
+                (ChannelLabel::FrontLeft, &[(-23.0, 10.0)]),
+                (ChannelLabel::FrontRight, &[(-23.0, 10.0)]),
+                (ChannelLabel::LowFrequency, &[(0.0, 10.0)]),
+            ],
+        );
+
+        assert_loudness(measured, -23.0);
+    }
+
+    #[test]
+    fn silence_is_below_the_absolute_gate() {
+        assert_eq!(stereo(48000, &[(-90.0, 5.0)]), f64::NEG_INFINITY);
+        assert_eq!(stereo(48000, &[(-23.0, 0.3)]), f64::NEG_INFINITY);
+    }
+}
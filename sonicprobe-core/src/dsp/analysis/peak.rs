@@ -1,3 +1,4 @@
+#[must_use]
 pub fn update_peak_value(current_peak: &f64, sample: &f64) -> Option<f64> {
     if *sample > *current_peak {
         return Some(*sample)
@@ -7,6 +8,8 @@ pub fn update_peak_value(current_peak: &f64, sample: &f64) -> Option<f64> {
 }
 
 #[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+#[allow(clippy::semicolon_if_nothing_returned)]
 mod tests {
     use super::*;
     use rand::Rng;
@@ -1,34 +1,67 @@
 use crate::{
-    floating_point_math::floating_point_utils::map_sum_lossless,
+    floating_point_math::floating_point_utils::LosslessSum,
     model::sonicprobe_error::SonicProbeError,
 };
 
+#[derive(Clone, Copy, Default)]
+pub struct RootMeanSquareMeter {
+    sum: LosslessSum,
+    count: usize,
+}
+
+impl RootMeanSquareMeter {
+    #[inline]
+    pub fn push_sample(&mut self, sample: f64) {
+        self.sum.add(sample.powi(2));
+        self.count += 1;
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.sum.add(other.sum.value());
+        self.count += other.count;
+    }
+
+    /// # Errors
+    ///
+    /// Fails when no sample was pushed or the sample count cannot be represented exactly as an `f64`.
+    #[allow(clippy::cast_sign_loss)]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn value(&self) -> Result<f64, SonicProbeError> {
+        if self.count == 0 {
+            return Err(SonicProbeError { 
+                message: "input slice is empty".to_owned(), 
+                location: format!("{}:{}", file!(), line!())
+            })
+        }
+
+        let size = self.count as f64;
+        if (size as usize) != self.count {
+            return Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: format!(
+                    "cannot represent usize value {} exactly in f64",
+                    self.count
+                ),
+            });
+        }
+
+        Ok((self.sum.value() / size).sqrt())
+    }
+}
+
+/// # Errors
+///
+/// Fails when `values` is empty or its length cannot be represented exactly as an `f64`.
 #[inline]
-#[allow(clippy::cast_sign_loss)]
-#[allow(clippy::cast_precision_loss)]
-#[allow(clippy::cast_possible_truncation)]
 pub fn compute_root_mean_square(values: &[f64]) -> Result<f64, SonicProbeError> {
-    if values.is_empty() {
-        return Err(SonicProbeError { 
-            message: "input slice is empty".to_owned(), 
-            location: format!("{}:{}", file!(), line!())
-        })
-    }
+    let mut meter = RootMeanSquareMeter::default();
 
-    let sum = map_sum_lossless(values, |x| x.powi(2));
-
-    let size = values.len() as f64;
-    if (size as usize) != values.len() {
-        return Err(SonicProbeError {
-            location: format!("{}:{}", file!(), line!()),
-            message: format!(
-                "cannot represent usize value {} exactly in f64",
-                values.len()
-            ),
-        });
+    for &value in values {
+        meter.push_sample(value);
     }
 
-    Ok((sum / size).sqrt())
+    meter.value()
 }
 
 #[cfg(test)]
@@ -106,4 +139,18 @@ mod tests {
         let result = compute_root_mean_square(&values).unwrap();
         assert_eq!(result, 4.082_482_904_638_63);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn meter_matches_across_blocks() {
+        let values: Vec<f64> = (0..1000).map(|i| (f64::from(i) * 0.37).sin()).collect();
+        let mut meter = RootMeanSquareMeter::default();
+
+        for block in values.chunks(77) {
+            for &value in block {
+                meter.push_sample(value);
+            }
+        }
+
+        assert_eq!(meter.value().unwrap(), compute_root_mean_square(&values).unwrap());
+    }
+}
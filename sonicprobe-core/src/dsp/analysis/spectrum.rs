@@ -0,0 +1,227 @@
+// Welch long-term average spectrum: windowed frames with 50% overlap, power averaged
+// per bin, then summarised in ISO 1/3-octave bands
+
+use std::sync::Arc;
+
+use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
+
+use crate::model::{
+    channel_layout::ChannelLabel,
+    frequency::Frequency,
+    sonicprobe_error::SonicProbeError,
+    spectrum::{Spectrum, SpectrumBand, SpectrumSettings},
+};
+
+const FIRST_BAND: i32 = -17;
+const LAST_BAND: i32 = 13;
+
+// ISO 266 nominal centres of the bands 1 kHz * 10^(n/10)
+const NOMINAL_CENTRES: [f64; 31] = [
+    20.0, 25.0, 31.5, 40.0, 50.0, 63.0, 80.0, 100.0, 125.0, 160.0, 200.0, 250.0, 315.0, 400.0, 500.0, 630.0, 800.0, 1000.0,
+    1250.0, 1600.0, 2000.0, 2500.0, 3150.0, 4000.0, 5000.0, 6300.0, 8000.0, 10000.0, 12500.0, 16000.0, 20000.0,
+];
+
+pub struct SpectrumMeter {
+    label: ChannelLabel,
+    settings: SpectrumSettings,
+    sample_rate: usize,
+    fft: Arc<dyn RealToComplex<f64>>,
+    window: Vec<f64>,
+    frame: Vec<f64>,
+    input: Vec<f64>,
+    output: Vec<Complex<f64>>,
+    scratch: Vec<Complex<f64>>,
+    power: Vec<f64>,
+    segments: usize,
+}
+
+impl SpectrumMeter {
+    #[must_use]
+    pub fn new(label: ChannelLabel, sample_rate: Frequency, settings: SpectrumSettings) -> Self {
+        let fft = RealFftPlanner::<f64>::new().plan_fft_forward(settings.fft_size());
+
+        Self {
+            label,
+            settings,
+            sample_rate: sample_rate.to_hz(),
+            window: settings.window().coefficients(settings.fft_size()),
+            frame: Vec::with_capacity(settings.fft_size()),
+            input: fft.make_input_vec(),
+            output: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            power: vec![0.0; settings.fft_size() / 2 + 1],
+            segments: 0,
+            fft,
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Fails when the FFT rejects a frame, which only happens if its buffers no longer match the
+    /// planned size.
+    pub fn push_samples(&mut self, samples: &[f64]) -> Result<(), SonicProbeError> {
+        let fft_size = self.settings.fft_size();
+        let mut remaining = samples;
+
+        while !remaining.is_empty() {
+            let taken = remaining.len().min(fft_size - self.frame.len());
+            self.frame.extend_from_slice(&remaining[..taken]);
+            remaining = &remaining[taken..];
+
+            if self.frame.len() == fft_size {
+                self.process_frame()?;
+                self.frame.drain(..fft_size / 2);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn process_frame(&mut self) -> Result<(), SonicProbeError> {
+        for ((input, sample), weight) in self.input.iter_mut().zip(&self.frame).zip(&self.window) {
+            *input = sample * weight;
+        }
+
+        self.fft.process_with_scratch(&mut self.input, &mut self.output, &mut self.scratch).map_err(|error| SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: format!("FFT failed: {error}"),
+        })?;
+
+        for (power, bin) in self.power.iter_mut().zip(&self.output) {
+            *power += bin.norm_sqr();
+        }
+        self.segments += 1;
+
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn value(&self) -> Spectrum {
+        let fft_size = self.settings.fft_size();
+        let bin_width = self.sample_rate as f64 / fft_size as f64;
+
+        let mut spectrum = Spectrum {
+            channel: self.label,
+            fft_size,
+            window: self.settings.window(),
+            segments: self.segments,
+            bin_width,
+            magnitudes: Vec::new(),
+            bands: Vec::new(),
+        };
+
+        if self.segments == 0 {
+            return spectrum;
+        }
+
+        // Peak amplitude of a bin centred sine, every bin but DC and Nyquist holds half of it
+        let coherent_gain: f64 = self.window.iter().sum();
+        let last_bin = self.power.len() - 1;
+        let amplitudes: Vec<f64> = self
+            .power
+            .iter()
+            .enumerate()
+            .map(|(bin, power)| {
+                let one_sided = if bin == 0 || bin == last_bin { 1.0 } else { 2.0 };
+                one_sided * (power / self.segments as f64).sqrt() / coherent_gain
+            })
+            .collect();
+
+        // Leakage spreads a sine over neighbouring bins, their summed power is the noise bandwidth
+        let noise_bandwidth = fft_size as f64 * self.window.iter().map(|weight| weight * weight).sum::<f64>() / coherent_gain.powi(2);
+        let nyquist = self.sample_rate as f64 / 2.0;
+
+        for (band, centre) in (FIRST_BAND..=LAST_BAND).zip(NOMINAL_CENTRES) {
+            let exact_centre = 1000.0 * 10f64.powf(f64::from(band) / 10.0);
+            let lower = exact_centre / 10f64.powf(0.05);
+            let upper = exact_centre * 10f64.powf(0.05);
+
+            if lower >= nyquist {
+                break;
+            }
+
+            let bins = (1..=last_bin).filter(|&bin| (lower..upper).contains(&(bin as f64 * bin_width)));
+            let (power, count) = bins.fold((0.0, 0), |(power, count), bin| (power + amplitudes[bin].powi(2), count + 1));
+
+            if count > 0 {
+                spectrum.bands.push(SpectrumBand { centre, level: 10.0 * (power / noise_bandwidth).log10() });
+            }
+        }
+
+        spectrum.magnitudes = amplitudes.into_iter().map(|amplitude| 20.0 * amplitude.log10()).collect();
+
+        spectrum
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::cast_precision_loss)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::model::spectrum::SpectrumWindow;
+
+    fn measure(samples: &[f64], settings: SpectrumSettings) -> Spectrum {
+        let mut meter = SpectrumMeter::new(ChannelLabel::Mono, Frequency::PRO_AUDIO, settings);
+        // Uneven blocks, as the decoders deliver them
+        for block in samples.chunks(1000) {
+            meter.push_samples(block).unwrap();
+        }
+        meter.value()
+    }
+
+    fn sine(frequency: f64, amplitude: f64, length: usize) -> Vec<f64> {
+        (0..length).map(|index| amplitude * (2.0 * PI * frequency * index as f64 / 48000.0).sin()).collect()
+    }
+
+    fn band(spectrum: &Spectrum, centre: f64) -> f64 {
+        spectrum.bands.iter().find(|band| band.centre == centre).unwrap().level
+    }
+
+    #[test]
+    fn full_scale_sine_reads_zero_dbfs() {
+        // Bin 128 of a 4096 point FFT at 48 kHz, 1.5 kHz
+        let frequency = 128.0 * 48000.0 / 4096.0;
+        let spectrum = measure(&sine(frequency, 1.0, 48000), SpectrumSettings::default());
+
+        assert_eq!(spectrum.segments, 22);
+        assert_eq!(spectrum.magnitudes.len(), 2049);
+        assert!(spectrum.magnitudes[128].abs() < 0.01);
+        assert!((spectrum.magnitudes[129] + 6.02).abs() < 0.01);
+        assert!(spectrum.magnitudes[200] < -100.0);
+        assert!(band(&spectrum, 1600.0).abs() < 0.01);
+        assert!(band(&spectrum, 400.0) < -100.0);
+    }
+
+    #[test]
+    fn band_level_does_not_depend_on_the_window() {
+        let samples = sine(997.0, 0.5, 96000);
+
+        for window in [SpectrumWindow::Rectangular, SpectrumWindow::Hann, SpectrumWindow::Hamming, SpectrumWindow::BlackmanHarris] {
+            let spectrum = measure(&samples, SpectrumSettings::new(8192, window).unwrap());
+            assert!((band(&spectrum, 1000.0) + 6.02).abs() < 0.1, "{window:?}");
+        }
+    }
+
+    #[test]
+    fn bands_stop_at_nyquist() {
+        let spectrum = measure(&sine(1000.0, 1.0, 16384), SpectrumSettings::default());
+
+        assert_eq!(spectrum.bands.last().unwrap().centre, 20000.0);
+        // The 20 Hz band (17.8 to 22.4 Hz) holds no bin at 11.72 Hz resolution
+        assert_eq!(spectrum.bands.first().unwrap().centre, 25.0);
+    }
+
+    #[test]
+    fn short_signal_has_no_spectrum() {
+        let spectrum = measure(&sine(1000.0, 1.0, 4000), SpectrumSettings::default());
+
+        assert_eq!(spectrum.segments, 0);
+        assert!(spectrum.magnitudes.is_empty());
+        assert!(spectrum.bands.is_empty());
+    }
+}
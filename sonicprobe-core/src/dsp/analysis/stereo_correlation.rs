@@ -2,22 +2,47 @@
 
 use crate::model::Signal;
 
+#[derive(Clone, Copy, Default)]
+pub struct StereoCorrelationMeter {
+    left_square_sum: f64,
+    right_square_sum: f64,
+    correlation: f64,
+}
+
+impl StereoCorrelationMeter {
+    #[inline]
+    pub fn push_frame(&mut self, left: f64, right: f64) {
+        self.correlation += left * right;
+        self.left_square_sum += left.powi(2);
+        self.right_square_sum += right.powi(2);
+    }
+
+    #[must_use]
+    pub fn value(&self) -> f64 {
+        self.correlation / (self.left_square_sum * self.right_square_sum).sqrt()
+    }
+
+    // Combines the sums of two consecutive programs as if they were measured in one pass
+    pub fn merge(&mut self, other: &Self) {
+        self.correlation += other.correlation;
+        self.left_square_sum += other.left_square_sum;
+        self.right_square_sum += other.right_square_sum;
+    }
+}
+
 #[inline]
+#[must_use]
 pub fn calculate_stereo_correlation(
     left: &Signal,
     right: &Signal
 ) -> f64 {
-    let mut left_square_sum: f64 = 0.0;
-    let mut right_square_sum: f64 = 0.0;
-    let mut correlation: f64 = 0.0;
-
-    for i in 0..left.len() {
-        correlation += (left[i]) * (right[i]);
-        left_square_sum += (left[i]).powi(2);
-        right_square_sum += (right[i]).powi(2);
+    let mut meter = StereoCorrelationMeter::default();
+
+    for (&left, &right) in left.iter().zip(right.iter()) {
+        meter.push_frame(left, right);
     }
 
-    correlation / (left_square_sum * right_square_sum).sqrt()
+    meter.value()
 }
 
 #[cfg(test)]
@@ -1,9 +1,78 @@
 use crate::model::{Signal, frequency::Frequency};
 
+// Streaming counterpart of `calculate_zero_crossing_rate`, crossings are bucketed
+// per second so the final partial second can be weighted the same way
+pub struct ZeroCrossingRateMeter {
+    sample_rate: usize,
+    previous_sign: Option<i8>,
+    position_in_second: usize,
+    completed_seconds: usize,
+    completed_crossings: usize,
+    pending_crossings: usize,
+    pending_boundary_crossing: bool,
+}
+
+impl ZeroCrossingRateMeter {
+    #[must_use]
+    pub const fn new(sample_rate: Frequency) -> Self {
+        Self {
+            sample_rate: sample_rate.to_hz(),
+            previous_sign: None,
+            position_in_second: 0,
+            completed_seconds: 0,
+            completed_crossings: 0,
+            pending_crossings: 0,
+            pending_boundary_crossing: false,
+        }
+    }
+
+    #[inline]
+    pub fn push_sample(&mut self, sample: f64) {
+        let sign = get_value_sign(sample);
+
+        if self.previous_sign.is_some_and(|previous| previous != sign) {
+            if self.position_in_second == 0 {
+                self.pending_boundary_crossing = true;
+            } else {
+                self.pending_crossings += 1;
+            }
+        }
+
+        self.previous_sign = Some(sign);
+        self.position_in_second += 1;
+
+        if self.position_in_second == self.sample_rate {
+            self.completed_crossings += self.pending_crossings + usize::from(self.pending_boundary_crossing);
+            self.completed_seconds += 1;
+            self.position_in_second = 0;
+            self.pending_crossings = 0;
+            self.pending_boundary_crossing = false;
+        }
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    #[must_use]
+    pub fn value(&self) -> usize {
+        let adjusted_reminder_crossing_rate = self.position_in_second as f64 / self.sample_rate as f64;
+
+        let mut reminder_crossing_rate = 0.0f64;
+        for _ in 0..self.pending_crossings {
+            reminder_crossing_rate += adjusted_reminder_crossing_rate;
+        }
+
+        let crossing_rate = self.completed_crossings + reminder_crossing_rate as usize;
+
+        crossing_rate / (1 + self.completed_seconds)
+    }
+}
+
 #[inline]
 #[allow(clippy::cast_sign_loss)]
 #[allow(clippy::cast_precision_loss)]
 #[allow(clippy::cast_possible_truncation)]
+#[must_use]
 pub fn calculate_zero_crossing_rate(samples: &Signal, sample_rate: Frequency) -> usize {
     let main_section_size = samples.len() - (samples.len() % sample_rate.to_hz());
     let adjusted_reminder_crossing_rate = (samples.len() - main_section_size) as f64 / f64::from(sample_rate);
@@ -35,3 +104,44 @@ const fn get_value_sign(value: f64) -> i8 {
 
     1
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn streamed(samples: &[f64], sample_rate: Frequency) -> usize {
+        let mut meter = ZeroCrossingRateMeter::new(sample_rate);
+
+        for &sample in samples {
+            meter.push_sample(sample);
+        }
+
+        meter.value()
+    }
+
+    #[test]
+    fn meter_matches_whole_signal() {
+        let sample_rate = Frequency::new(1000).unwrap();
+
+        for length in [0, 1, 999, 1000, 1001, 2500, 3000, 4321] {
+            let samples: Arc<[f64]> = (0..length).map(|i| (f64::from(i) * 0.731).sin() + 0.1).collect();
+
+            assert_eq!(
+                streamed(&samples, sample_rate),
+                calculate_zero_crossing_rate(&samples, sample_rate),
+                "length {length}"
+            );
+        }
+    }
+
+    #[test]
+    fn crossing_on_second_boundary() {
+        let sample_rate = Frequency::new(4).unwrap();
+        let samples: Arc<[f64]> = Arc::from(vec![1.0, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0, -1.0, 1.0, -1.0]);
+
+        assert_eq!(streamed(&samples, sample_rate), calculate_zero_crossing_rate(&samples, sample_rate));
+    }
+}
@@ -1,103 +1,247 @@
-use crate::{
-    floating_point_math::dot_product::dot_product,
-    model::{HD_COEFFS, SD_COEFFS, frequency::Frequency, sonicprobe_error::SonicProbeError},
-};
+// Polyphase interpolator for true-peak metering: every rate up to 192 kHz is oversampled
+// to at least 192 kHz (and at least 2x), higher rates are measured as they are
 
-use std::f64;
+use crate::model::frequency::Frequency;
 
-enum FilterPhase {
-    Two,
-    Four,
-}
+use std::f64::consts::PI;
+
+const TARGET_RATE: usize = 192_000;
+
+// Taps per phase, a multiple of the 4 accumulators used while filtering
+const WINDOW_SIZE: usize = 36;
+const ACCUMULATORS: usize = 4;
+const KAISER_BETA: f64 = 7.0;
 
-pub struct Upscaler<'a> {
-    phase_matrix: &'a [[f64; 12]],
+pub struct Upscaler {
+    phase_matrix: Vec<[f64; WINDOW_SIZE]>,
+    // Starts as silence so the first samples are interpolated like any other
+    history: [f64; WINDOW_SIZE],
     buffer: Vec<f64>,
-    phases: FilterPhase,
-    original_samples_index: usize,
-    buffer_index: usize,
-    samples: &'a [f64]
 }
 
-impl<'a> Upscaler<'a> {
-    pub fn new(source: &'a[f64], source_sample_rate: Frequency) -> Result<Self, SonicProbeError> {
-        let phases: FilterPhase = match source_sample_rate {
-            Frequency::CdQuality | Frequency::ProAudio => FilterPhase::Four,
-            Frequency::HiResDouble | Frequency::DvdAudio => FilterPhase::Two,
-            Frequency::StudioMaster | Frequency::UltraHiRes => {
-                return Err(SonicProbeError {
-                    location: format!("{}:{}", file!(), line!()),
-                    message: "upscaling for 176,400Hz and 192,000Hz not implemented".to_owned(),
-                });
+impl Upscaler {
+    pub fn new(source_sample_rate: Frequency) -> Self {
+        let phase_matrix = phase_matrix(oversampling_factor(source_sample_rate.to_hz()));
+
+        Self {
+            history: [0.0; WINDOW_SIZE],
+            buffer: vec![0.0; phase_matrix.len()],
+            phase_matrix,
+        }
+    }
+
+    #[inline]
+    pub fn push_sample(&mut self, sample: f64) -> &[f64] {
+        self.history.copy_within(1.., 0);
+        self.history[WINDOW_SIZE - 1] = sample;
+
+        let window = &self.history;
+
+        for (coefficients, output) in self.phase_matrix.iter().zip(self.buffer.iter_mut()) {
+            let mut sums = [0.0; ACCUMULATORS];
+            for (coefficients, samples) in coefficients.chunks_exact(ACCUMULATORS).zip(window.chunks_exact(ACCUMULATORS)) {
+                for lane in 0..ACCUMULATORS {
+                    sums[lane] += coefficients[lane] * samples[lane];
+                }
             }
-        };
-
-        let phase_matrix: &[[f64; 12]] = match source_sample_rate {
-            Frequency::CdQuality | Frequency::ProAudio => &SD_COEFFS,
-            Frequency::HiResDouble | Frequency::DvdAudio => &HD_COEFFS,
-            Frequency::StudioMaster | Frequency::UltraHiRes => {
-                return Err(SonicProbeError {
-                    location: format!("{}:{}", file!(), line!()),
-                    message: "upscaling for 176,400Hz and 192,000Hz not implemented".to_owned(),
-                });
+            *output = sums.iter().sum();
+        }
+
+        &self.buffer
+    }
+
+    // Trailing silence moves the last samples of the stream through the centre of the filter
+    pub fn flush(&mut self) -> Vec<f64> {
+        (0..WINDOW_SIZE).flat_map(|_| self.push_sample(0.0).to_vec()).collect()
+    }
+}
+
+// 176.4 and 192 kHz still get 2x, the smallest factor reaching the target otherwise
+const fn oversampling_factor(sample_rate: usize) -> usize {
+    if sample_rate > TARGET_RATE {
+        1
+    } else {
+        let factor = TARGET_RATE.div_ceil(sample_rate);
+        if factor < 2 { 2 } else { factor }
+    }
+}
+
+// Kaiser windowed sinc cut at the source Nyquist frequency, split into one filter per
+// output phase and stored oldest sample first to match the history window.
+// Phase 0 is centred on an input sample, so it reproduces the input exactly
+#[allow(clippy::cast_precision_loss)]
+fn phase_matrix(factor: usize) -> Vec<[f64; WINDOW_SIZE]> {
+    let length = (WINDOW_SIZE * factor) as f64;
+    let centre = (WINDOW_SIZE / 2 * factor) as f64;
+
+    let prototype = |index: usize| {
+        let offset = index as f64 - centre;
+        let position = offset / (length / 2.0);
+        let window = bessel_i0(KAISER_BETA * (1.0 - position * position).max(0.0).sqrt()) / bessel_i0(KAISER_BETA);
+
+        sinc(offset / factor as f64) * window
+    };
+
+    (0..factor)
+        .map(|phase| {
+            let mut coefficients = [0.0; WINDOW_SIZE];
+            for (tap, coefficient) in coefficients.iter_mut().enumerate() {
+                *coefficient = prototype((WINDOW_SIZE - 1 - tap) * factor + phase);
             }
-        };
-
-        let buffer = match phases {
-            FilterPhase::Two => vec![0.0, 0.0],
-            FilterPhase::Four => vec![0.0, 0.0, 0.0, 0.0]
-        };
-
-        let buffer_index= match phases {
-            FilterPhase::Two => 2,
-            FilterPhase::Four => 4
-        };
-
-        Ok(Self { 
-            phase_matrix, 
-            buffer,
-            phases,
-            original_samples_index: 0,
-            buffer_index,
-            samples: source
+            coefficients
         })
+        .collect()
+}
+
+// Exact zeros at whole numbers keep the input samples untouched by the first phase
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else if x.fract() == 0.0 {
+        0.0
+    } else {
+        (PI * x).sin() / (PI * x)
     }
+}
 
-    #[inline]
-    pub fn next_sample(&mut self) -> Option<&f64> {
-        if self.buffer_index + 1 < self.buffer.len() {
-            self.buffer_index = self.buffer_index + 1;
-            return Some(&self.buffer[self.buffer_index])
-        }
+// Zeroth order modified Bessel function of the first kind, power series
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+
+    while term > sum * 1e-12 {
+        term *= (x / (2.0 * k)).powi(2);
+        sum += term;
+        k += 1.0;
+    }
+
+    sum
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::unwrap_used)]
+mod filter_selection_tests {
+    use super::*;
+
+    fn upsample(samples: &[f64], rate: u32) -> Vec<f64> {
+        let mut upscaler = Upscaler::new(Frequency::new(rate).unwrap());
+
+        let mut upsampled: Vec<f64> = samples.iter().flat_map(|&sample| upscaler.push_sample(sample).to_vec()).collect();
+        upsampled.extend(upscaler.flush());
+        upsampled
+    }
 
-        if self.original_samples_index + 1 < self.samples.len() - 12 {
-            self.update_buffer();
-            self.buffer_index = 0;
+    fn peak(samples: &[f64], rate: u32) -> f64 {
+        upsample(samples, rate).iter().fold(0.0f64, |peak, sample| peak.max(sample.abs()))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn true_peak(rate: u32, frequency: f64, phase: f64) -> f64 {
+        let samples: Vec<f64> =
+            (0..rate / 10).map(|i| 0.5 * (2.0 * PI * frequency * f64::from(i) / f64::from(rate) + phase).sin()).collect();
+
+        // The sine is cut off hard at both ends, the ringing there is left out
+        let factor = oversampling_factor(rate as usize);
+        let upsampled = upsample(&samples, rate);
+        let steady = &upsampled[2 * WINDOW_SIZE * factor..upsampled.len() - 2 * WINDOW_SIZE * factor];
+
+        20.0 * steady.iter().fold(0.0f64, |peak, sample| peak.max(sample.abs())).log10()
+    }
 
-            return Some(&self.buffer[0])
+    #[test]
+    fn every_rate_reaches_the_target() {
+        assert_eq!(oversampling_factor(8000), 24);
+        assert_eq!(oversampling_factor(44100), 5);
+        assert_eq!(oversampling_factor(48000), 4);
+        assert_eq!(oversampling_factor(96000), 2);
+        assert_eq!(oversampling_factor(176_400), 2);
+        assert_eq!(oversampling_factor(192_000), 2);
+        assert_eq!(oversampling_factor(352_800), 1);
+    }
+
+    #[test]
+    fn output_count_follows_the_factor() {
+        assert_eq!(upsample(&[0.5; 64], 48000).len(), 4 * (64 + WINDOW_SIZE));
+        assert_eq!(upsample(&[0.5; 64], 192_000).len(), 2 * (64 + WINDOW_SIZE));
+    }
+
+    #[test]
+    fn dxd_rates_pass_through() {
+        let samples: Vec<f64> = (0..64).map(f64::from).collect();
+        let upsampled = upsample(&samples, 352_800);
+
+        // Output is delayed by half a window, the history starts as silence
+        assert_eq!(upsampled[..WINDOW_SIZE / 2], [0.0; WINDOW_SIZE / 2]);
+        assert_eq!(upsampled[WINDOW_SIZE / 2..WINDOW_SIZE / 2 + 64], samples[..]);
+    }
+
+    #[test]
+    fn first_phase_reproduces_the_input() {
+        let samples: Vec<f64> = (0..80).map(|i| (f64::from(i) * 0.9).sin()).collect();
+        let upsampled = upsample(&samples, 44100);
+
+        for (index, sample) in upsampled.iter().step_by(5).enumerate() {
+            let expected = index.checked_sub(WINDOW_SIZE / 2).and_then(|index| samples.get(index)).unwrap_or(&0.0);
+            assert!((sample - expected).abs() < 1e-12);
         }
+    }
+
+    #[test]
+    fn peak_in_the_first_sample() {
+        let mut samples = vec![0.0; 64];
+        samples[0] = 0.9;
+
+        assert!((peak(&samples, 44100) - 0.9).abs() < 1e-12);
+        assert!((peak(&samples, 352_800) - 0.9).abs() < 1e-12);
+    }
+
+    #[test]
+    fn peak_in_the_last_sample() {
+        let mut samples = vec![0.0; 64];
+        samples[63] = -0.9;
 
-        None
-    }
-
-    fn update_buffer(&mut self) {
-        let current_index = self.original_samples_index;
-        let window = &self.samples[current_index..current_index+12];
-    
-        match self.phases {
-            FilterPhase::Two =>{
-                dot_product(&self.phase_matrix[0], window, &mut self.buffer[0]);
-                dot_product(&self.phase_matrix[1], window, &mut self.buffer[1]);
-            },
-            FilterPhase::Four => {
-                dot_product(&self.phase_matrix[0], window, &mut self.buffer[0]);
-                dot_product(&self.phase_matrix[1], window, &mut self.buffer[1]);
-                dot_product(&self.phase_matrix[2], window, &mut self.buffer[2]);
-                dot_product(&self.phase_matrix[3], window, &mut self.buffer[3]);
+        assert!((peak(&samples, 44100) - 0.9).abs() < 1e-12);
+        assert!((peak(&samples, 352_800) - 0.9).abs() < 1e-12);
+    }
+
+    #[test]
+    fn streams_shorter_than_the_window() {
+        let samples: Vec<f64> = (0..10).map(|i| 0.5 * (f64::from(i) * 1.3).sin()).collect();
+        let sample_peak = samples.iter().fold(0.0f64, |peak, sample| peak.max(sample.abs()));
+
+        assert_eq!(upsample(&samples, 48000).len(), 4 * (10 + WINDOW_SIZE));
+        assert!(peak(&samples, 48000) >= sample_peak - 1e-12);
+        assert!((peak(&samples, 352_800) - sample_peak).abs() < 1e-12);
+    }
+
+    #[test]
+    fn phases_have_unity_gain() {
+        for factor in [2, 4, 5] {
+            for coefficients in phase_matrix(factor) {
+                assert!((coefficients.iter().sum::<f64>() - 1.0).abs() < 1e-3);
             }
         }
+    }
 
-        self.original_samples_index = self.original_samples_index + 1;
+    // EBU Tech 3341 cases 15 to 19: a -6 dBFS sine at a quarter of the sample rate read
+    // with several phase offsets must measure -6 dBTP, +0.2/-0.4 dB
+    #[test]
+    fn quarter_rate_sine_at_every_phase() {
+        for phase in [0.0, 45.0, 60.0, 67.5, 88.2] {
+            let measured = true_peak(48000, 12000.0, f64::to_radians(phase));
+            assert!((-6.42..=-5.82).contains(&measured), "{phase}: {measured}");
+        }
+    }
+
+    #[test]
+    fn inter_sample_peaks_at_every_rate() {
+        for rate in [44100, 48000, 88200, 96000, 176_400, 192_000] {
+            let frequency = f64::from(rate) / 4.0 * 0.9;
+            let measured = true_peak(rate, frequency, f64::to_radians(45.0));
+            assert!((-6.42..=-5.82).contains(&measured), "{rate}: {measured}");
+        }
     }
 }
 
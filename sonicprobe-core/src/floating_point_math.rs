@@ -1,2 +1 @@
 pub mod floating_point_utils;
-pub mod dot_product;
\ No newline at end of file
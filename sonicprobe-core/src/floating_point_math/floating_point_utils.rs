@@ -1,14 +1,30 @@
+// Compensated sum whose state can be carried across blocks of samples
+#[derive(Clone, Copy, Default)]
+pub struct LosslessSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl LosslessSum {
+    #[inline]
+    pub fn add(&mut self, value: f64) {
+        self.compensation = ((self.sum + (value - self.compensation)) - self.sum) - (value - self.compensation);
+        self.sum += value - self.compensation;
+    }
+
+    #[must_use]
+    pub const fn value(self) -> f64 {
+        self.sum
+    }
+}
+
 #[inline]
 pub fn map_sum_lossless<T: Fn(f64) -> f64>(list: &[f64], map_fn: T) -> f64 {
-    let mut sum = 0.0;
-    let mut compensation = 0.0;
-    
+    let mut sum = LosslessSum::default();
+
     for &current_value in list {
-        let mapped_value = map_fn(current_value);
-        compensation = ((sum + (mapped_value - compensation)) - sum) - (mapped_value - compensation);
-        sum += mapped_value - compensation;
+        sum.add(map_fn(current_value));
     }
 
-    sum
+    sum.value()
 }
-
@@ -1,7 +1,11 @@
+mod decoders;
 mod dsp;
 mod floating_point_math;
 mod model;
+mod tagging;
 
+pub use decoders::*;
 pub use dsp::*;
 pub use floating_point_math::*;
-pub use model::*;
\ No newline at end of file
+pub use model::*;
+pub use tagging::*;
\ No newline at end of file
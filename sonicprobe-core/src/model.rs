@@ -1,12 +1,20 @@
 use std::sync::Arc;
 
+pub mod album;
 pub mod audio_file;
+pub mod audio_signal;
 pub mod bit_depth;
 pub mod builders;
 pub mod channel;
+pub mod channel_layout;
+pub mod compliance;
 pub mod frequency;
+pub mod loudness;
+pub mod lossy_transcode;
+pub mod metadata;
+pub mod replay_gain;
 pub mod sonicprobe_error;
-pub mod stereo_signal;
+pub mod spectrum;
 pub mod decibel;
 pub mod dynamic_range;
 
@@ -18,17 +26,3 @@ pub const MAX_8_BIT: f64 = 127.0;
 pub const MAX_16_BIT: f64 = 32767.0;
 pub const MAX_24_BIT: f64 = 8_388_607.0;
 pub const MAX_32_BIT: f64 = 2_147_483_647.0;
-
-#[allow(clippy::unreadable_literal)]
-pub const SD_COEFFS: [[f64; 12]; 4] = [
-    [0.0017089843750, 0.0109863281250, -0.0196533203125, 0.0332031250000, -0.0594482421875, 0.1373291015625, 0.9721679687500, -0.1022949218750, 0.0476074218750, -0.0266113281250, 0.0148925781250, -0.0083007812500],
-    [-0.0291748046875, 0.0292968750000, -0.0517578125000, 0.0891113281250, -0.1665039062500, 0.4650878906250, 0.7797851562500, -0.2003173828125, 0.1015625000000, -0.0582275390625, 0.0330810546875, -0.0189208984375],
-    [-0.0189208984375, 0.0330810546875, -0.0582275390625, 0.1015625000000, -0.2003173828125, 0.7797851562500, 0.4650878906250, -0.1665039062500, 0.0891113281250, -0.0517578125000, 0.0292968750000, -0.0291748046875],
-    [-0.0083007812500, 0.0148925781250, -0.0266113281250, 0.0476074218750, -0.1022949218750, 0.9721679687500, 0.1373291015625, -0.0594482421875, 0.0332031250000, -0.0196533203125, 0.0109863281250, 0.0017089843750]
-];
-
-#[allow(clippy::unreadable_literal)]
-pub const HD_COEFFS: [[f64; 12]; 2] = [
-    [-0.0005187988281, -0.0053405761719, -0.0203857421875, -0.0561523437500, -0.1370849609375, -0.4033050537109, 1.2660980224609, 0.2207641601562, 0.0880126953125, 0.0347442626953, 0.0110626220703, 0.0021057128906],
-    [0.0021057128906, 0.0110626220703, 0.0347442626953, 0.0880126953125, 0.2207641601562, 1.2660980224609, -0.4033050537109, -0.1370849609375, -0.0561523437500, -0.0203857421875, -0.0053405761719, -0.0005187988281],
-];
@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+use crate::model::{decibel::Decibel, dynamic_range::DynamicRange, loudness::Loudness, replay_gain::ReplayGain, Milliseconds};
+
+#[derive(Clone, Copy, Serialize)]
+pub struct Album {
+    pub tracks: usize,
+    pub duration: Milliseconds,
+    pub dr: DynamicRange,
+    pub peak: Decibel,
+    pub true_peak: Decibel,
+    pub rms: Decibel,
+    pub correlation: Option<f64>,
+    pub integrated_loudness: Loudness,
+    pub replay_gain: Option<ReplayGain>,
+}
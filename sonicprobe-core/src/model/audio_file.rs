@@ -1,25 +1,70 @@
 use serde::Serialize;
 
-use crate::model::{bit_depth::BitDepth, channel::Channel, decibel::Decibel, frequency::Frequency, Milliseconds};
+use crate::model::{
+    bit_depth::{BitDepth, DepthUsage},
+    channel::{Channel, ChannelPair},
+    compliance::ComplianceReport,
+    decibel::Decibel,
+    dynamic_range::DynamicRange,
+    frequency::Frequency,
+    lossy_transcode::LossyTranscode,
+    loudness::{Loudness, LoudnessPoint, LoudnessRange, PeakToLoudness},
+    metadata::Metadata,
+    replay_gain::{ReplayGain, ReplayGainPeak},
+    spectrum::Spectrum,
+    Milliseconds,
+};
 
 #[derive(Serialize)]
 pub struct AudioFile {
-    pub left: Channel,
-    pub right: Channel,
+    pub channels: Vec<Channel>,
+    pub channel_pairs: Vec<ChannelPair>,
     pub samples_per_channel: usize,
     pub sample_rate: Frequency,
     pub duration: Milliseconds,
-    pub stereo_correlation: f64,
-    pub channels: u8,
     pub depth: BitDepth,
-    pub true_depth: u8,
+    pub true_depth: DepthUsage,
+    pub lossy_transcode: LossyTranscode,
+    pub dr: DynamicRange,
+    pub integrated_loudness: Loudness,
+    pub loudness_range: LoudnessRange,
+    pub max_short_term_loudness: Loudness,
+    pub peak_to_loudness: PeakToLoudness,
+    pub spectrum: Vec<Spectrum>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub loudness_series: Vec<LoudnessPoint>,
+    #[serde(skip)]
+    pub(crate) loudness_blocks: Vec<f64>,
+    pub replay_gain: Option<ReplayGain>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub compliance: Vec<ComplianceReport>,
+    pub metadata: Metadata,
 }
 
 impl AudioFile {
-    pub fn rms_balance(&self) -> Decibel {
-        self.left.rms() - self.right.rms()
+    #[must_use]
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
     }
 
+    #[must_use]
+    pub const fn dr(&self) -> DynamicRange {
+        self.dr
+    }
+
+    #[must_use]
+    pub fn replay_gain_with(&self, peak: ReplayGainPeak) -> Option<ReplayGain> {
+        let level = match peak {
+            ReplayGainPeak::Sample => Channel::peak,
+            ReplayGainPeak::True => Channel::true_peak,
+        };
+
+        let loudest = self.channels.iter().map(level).max_by(|a, b| a.get_value().total_cmp(&b.get_value()));
+
+        ReplayGain::new(self.integrated_loudness, loudest.unwrap_or(Decibel::new(0.0)))
+    }
+
+    #[must_use]
     pub fn to_json(&self) -> String {
         match serde_json::to_string_pretty(&self) {
             Ok(value) => value,
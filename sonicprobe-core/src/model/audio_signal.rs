@@ -0,0 +1,15 @@
+use crate::model::{Signal, bit_depth::BitDepth, channel_layout::ChannelLabel, frequency::Frequency};
+
+pub struct AudioSignal {
+    pub channels: Vec<Signal>,
+    pub layout: Vec<ChannelLabel>,
+    pub sample_rate: Frequency,
+    pub depth: BitDepth,
+}
+
+impl AudioSignal {
+    #[must_use]
+    pub fn samples_per_channel(&self) -> usize {
+        self.channels.first().map_or(0, |channel| channel.len())
+    }
+}
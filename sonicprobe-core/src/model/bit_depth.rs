@@ -2,12 +2,22 @@ use serde::{Serialize, Serializer};
 
 use crate::model::sonicprobe_error::SonicProbeError;
 
-#[derive(Clone, Copy)]
+const MIN_BITS: u8 = 4;
+const MAX_BITS: u8 = 32;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum BitDepth {
-    Legacy,
-    CdStandard,
-    Professional,
-    StudioMaster,
+    Integer(u8),
+    Float(u8),
+}
+
+// Bits actually carried by the samples, see `calculate_true_depth`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepthUsage {
+    Integer(u8),
+    QuantisedFloat(u8),
+    Mantissa(u8),
 }
 
 impl Serialize for BitDepth {
@@ -20,12 +30,19 @@ impl Serialize for BitDepth {
 }
 
 impl BitDepth {
+    pub const LEGACY: Self = Self::Integer(8);
+    pub const CD_STANDARD: Self = Self::Integer(16);
+    pub const PROFESSIONAL: Self = Self::Integer(24);
+    pub const STUDIO_MASTER: Self = Self::Integer(32);
+    pub const FLOAT: Self = Self::Float(32);
+    pub const DOUBLE: Self = Self::Float(64);
+
+    /// # Errors
+    ///
+    /// Fails when `value` lies outside 4 to 32 bits.
     pub fn new(value: u32) -> Result<Self, SonicProbeError> {
-        match value {
-            8 => Ok(Self::Legacy),
-            16 => Ok(Self::CdStandard),
-            24 => Ok(Self::Professional),
-            32 => Ok(Self::StudioMaster),
+        match u8::try_from(value) {
+            Ok(bits) if (MIN_BITS..=MAX_BITS).contains(&bits) => Ok(Self::Integer(bits)),
             _ => Err(SonicProbeError {
                 location: format!("{}:{}", file!(), line!()),
                 message: format!("Currently {value} bit depth is not supported"),
@@ -33,116 +50,224 @@ impl BitDepth {
         }
     }
 
+    /// # Errors
+    ///
+    /// Fails when `value` is neither 32 nor 64 bits.
+    pub fn new_float(value: u32) -> Result<Self, SonicProbeError> {
+        match value {
+            32 => Ok(Self::FLOAT),
+            64 => Ok(Self::DOUBLE),
+            _ => Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: format!("Currently {value} bit floating point samples are not supported"),
+            }),
+        }
+    }
+
+    #[must_use]
     pub const fn to_bits(self) -> u8 {
         match self {
-            Self::Legacy => 8,
-            Self::CdStandard => 16,
-            Self::Professional => 24,
-            Self::StudioMaster => 32,
+            Self::Integer(bits) | Self::Float(bits) => bits,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_float(self) -> bool {
+        matches!(self, Self::Float(_))
+    }
+
+    // Largest positive integer sample, 2^(n-1) - 1, floats are already normalized
+    #[must_use]
+    pub fn full_scale(self) -> f64 {
+        match self {
+            Self::Integer(bits) => f64::from((1u32 << (bits - 1)) - 1),
+            Self::Float(_) => 1.0,
+        }
+    }
+
+    #[must_use]
+    pub fn description(self) -> String {
+        match self {
+            Self::Integer(8) => " 8  bit - Legacy format".to_owned(),
+            Self::Integer(12) => "12  bit - Vintage sampler".to_owned(),
+            Self::Integer(16) => "16  bit - CD standard".to_owned(),
+            Self::Integer(20) => "20  bit - Broadcast".to_owned(),
+            Self::Integer(24) => "24  bit - Professional standard".to_owned(),
+            Self::Integer(32) => "32  bit - Studio master quality".to_owned(),
+            Self::Integer(bits) => format!("{bits:>2}  bit"),
+            Self::Float(32) => "32  bit float - DAW bounce".to_owned(),
+            Self::Float(bits) => format!("{bits:>2}  bit float"),
         }
     }
+}
 
-    pub const fn description(self) -> &'static str {
+impl DepthUsage {
+    #[must_use]
+    pub const fn to_bits(self) -> u8 {
         match self {
-            Self::Legacy => " 8  bit - Legacy format",
-            Self::CdStandard => "16  bit - CD standard",
-            Self::Professional => "24  bit - Professional standard",
-            Self::StudioMaster => "32  bit - Studio master quality",
+            Self::Integer(bits) | Self::QuantisedFloat(bits) | Self::Mantissa(bits) => bits,
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::float_cmp)]
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use crate::model::{MAX_8_BIT, MAX_16_BIT, MAX_24_BIT, MAX_32_BIT};
 
     #[test]
     fn new_legacy() {
-        assert!(matches!(BitDepth::new(8).unwrap(), BitDepth::Legacy));
+        assert_eq!(BitDepth::new(8).unwrap(), BitDepth::LEGACY);
     }
 
     #[test]
     fn new_cd_standard() {
-        assert!(matches!(BitDepth::new(16).unwrap(), BitDepth::CdStandard));
+        assert_eq!(BitDepth::new(16).unwrap(), BitDepth::CD_STANDARD);
     }
 
     #[test]
     fn new_professional() {
-        assert!(matches!(BitDepth::new(24).unwrap(), BitDepth::Professional));
+        assert_eq!(BitDepth::new(24).unwrap(), BitDepth::PROFESSIONAL);
     }
 
     #[test]
     fn new_studio_master() {
-        assert!(matches!(BitDepth::new(32).unwrap(), BitDepth::StudioMaster));
+        assert_eq!(BitDepth::new(32).unwrap(), BitDepth::STUDIO_MASTER);
+    }
+
+    #[test]
+    fn new_non_byte_aligned() {
+        assert_eq!(BitDepth::new(12).unwrap().to_bits(), 12);
+        assert_eq!(BitDepth::new(20).unwrap().to_bits(), 20);
+        assert_eq!(BitDepth::new(4).unwrap().to_bits(), 4);
     }
 
     #[test]
     fn new_unsupported() {
-        assert!(BitDepth::new(12).is_err());
+        assert!(BitDepth::new(3).is_err());
+        assert!(BitDepth::new(33).is_err());
+        assert!(BitDepth::new(300).is_err());
     }
 
     #[test]
     fn to_bits_legacy() {
-        assert_eq!(BitDepth::Legacy.to_bits(), 8);
+        assert_eq!(BitDepth::LEGACY.to_bits(), 8);
     }
 
     #[test]
     fn to_bits_cd_standard() {
-        assert_eq!(BitDepth::CdStandard.to_bits(), 16);
+        assert_eq!(BitDepth::CD_STANDARD.to_bits(), 16);
     }
 
     #[test]
     fn to_bits_professional() {
-        assert_eq!(BitDepth::Professional.to_bits(), 24);
+        assert_eq!(BitDepth::PROFESSIONAL.to_bits(), 24);
     }
 
     #[test]
     fn to_bits_studio_master() {
-        assert_eq!(BitDepth::StudioMaster.to_bits(), 32);
+        assert_eq!(BitDepth::STUDIO_MASTER.to_bits(), 32);
+    }
+
+    #[test]
+    fn full_scale_legacy() {
+        assert_eq!(BitDepth::LEGACY.full_scale(), MAX_8_BIT);
+    }
+
+    #[test]
+    fn full_scale_byte_aligned() {
+        assert_eq!(BitDepth::CD_STANDARD.full_scale(), MAX_16_BIT);
+        assert_eq!(BitDepth::PROFESSIONAL.full_scale(), MAX_24_BIT);
+    }
+
+    #[test]
+    fn full_scale_studio_master() {
+        assert_eq!(BitDepth::STUDIO_MASTER.full_scale(), MAX_32_BIT);
+    }
+
+    #[test]
+    fn full_scale_non_byte_aligned() {
+        assert_eq!(BitDepth::new(12).unwrap().full_scale(), 2047.0);
+        assert_eq!(BitDepth::new(20).unwrap().full_scale(), 524_287.0);
+        assert_eq!(BitDepth::new(4).unwrap().full_scale(), 7.0);
     }
 
     #[test]
     fn description_legacy() {
-        assert_eq!(BitDepth::Legacy.description(), " 8  bit - Legacy format");
+        assert_eq!(BitDepth::LEGACY.description(), " 8  bit - Legacy format");
     }
 
     #[test]
     fn description_cd_standard() {
-        assert_eq!(BitDepth::CdStandard.description(), "16  bit - CD standard");
+        assert_eq!(BitDepth::CD_STANDARD.description(), "16  bit - CD standard");
     }
 
     #[test]
     fn description_professional() {
-        assert_eq!(BitDepth::Professional.description(), "24  bit - Professional standard");
+        assert_eq!(BitDepth::PROFESSIONAL.description(), "24  bit - Professional standard");
     }
 
     #[test]
     fn description_studio_master() {
-        assert_eq!(BitDepth::StudioMaster.description(), "32  bit - Studio master quality");
+        assert_eq!(BitDepth::STUDIO_MASTER.description(), "32  bit - Studio master quality");
+    }
+
+    #[test]
+    fn description_unnamed_depth() {
+        assert_eq!(BitDepth::new(14).unwrap().description(), "14  bit");
+    }
+
+    #[test]
+    fn new_float() {
+        assert_eq!(BitDepth::new_float(32).unwrap(), BitDepth::FLOAT);
+        assert_eq!(BitDepth::new_float(64).unwrap(), BitDepth::DOUBLE);
+        assert!(BitDepth::new_float(16).is_err());
+    }
+
+    #[test]
+    fn float_properties() {
+        assert!(BitDepth::DOUBLE.is_float());
+        assert!(!BitDepth::STUDIO_MASTER.is_float());
+        assert_eq!(BitDepth::FLOAT.full_scale(), 1.0);
+        assert_eq!(BitDepth::DOUBLE.to_bits(), 64);
+        assert_eq!(BitDepth::DOUBLE.description(), "64  bit float");
+    }
+
+    #[test]
+    fn serialize_depth_usage() {
+        let json = serde_json::to_string(&DepthUsage::QuantisedFloat(24)).unwrap();
+        assert_eq!(json, r#"{"quantised_float":24}"#);
     }
 
     #[test]
     fn serialize_legacy() {
-        let json = serde_json::to_string(&BitDepth::Legacy).unwrap();
+        let json = serde_json::to_string(&BitDepth::LEGACY).unwrap();
         assert_eq!(json, "8");
     }
 
     #[test]
     fn serialize_cd_standard() {
-        let json = serde_json::to_string(&BitDepth::CdStandard).unwrap();
+        let json = serde_json::to_string(&BitDepth::CD_STANDARD).unwrap();
         assert_eq!(json, "16");
     }
 
     #[test]
     fn serialize_professional() {
-        let json = serde_json::to_string(&BitDepth::Professional).unwrap();
+        let json = serde_json::to_string(&BitDepth::PROFESSIONAL).unwrap();
         assert_eq!(json, "24");
     }
 
     #[test]
     fn serialize_studio_master() {
-        let json = serde_json::to_string(&BitDepth::StudioMaster).unwrap();
+        let json = serde_json::to_string(&BitDepth::STUDIO_MASTER).unwrap();
         assert_eq!(json, "32");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn serialize_non_byte_aligned() {
+        let json = serde_json::to_string(&BitDepth::new(20).unwrap()).unwrap();
+        assert_eq!(json, "20");
+    }
+}
@@ -1,3 +1,3 @@
 pub mod channel_builder;
-pub mod stereo_signal_builder;
-pub mod audio_file_builder;
\ No newline at end of file
+pub mod audio_file_builder;
+pub mod album_builder;
\ No newline at end of file
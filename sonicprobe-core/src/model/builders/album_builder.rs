@@ -0,0 +1,141 @@
+use crate::{
+    dsp::analysis::{
+        loudness::gated_loudness, root_mean_square::RootMeanSquareMeter,
+        stereo_correlation::StereoCorrelationMeter,
+    },
+    model::{
+        album::Album,
+        audio_file::AudioFile,
+        channel::Channel,
+        channel_layout::ChannelLabel,
+        decibel::Decibel,
+        dynamic_range::DynamicRange,
+        loudness::Loudness,
+        replay_gain::{ReplayGain, ReplayGainPeak},
+        sonicprobe_error::SonicProbeError,
+    },
+};
+
+/// Levels are combined from the raw sums of every track so that the album RMS and
+/// correlation describe the concatenated program rather than an average of dB values,
+/// the gating blocks of every track are pooled for the album loudness
+///
+/// # Errors
+///
+/// Fails when `tracks` is empty or holds no samples, or when the pooled sample count cannot be
+/// represented exactly as an `f64`.
+pub fn album_from_tracks(tracks: &[&AudioFile], replay_gain_peak: ReplayGainPeak) -> Result<Album, SonicProbeError> {
+    if tracks.is_empty() {
+        return Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "an album needs at least one track".to_owned(),
+        });
+    }
+
+    let channels = || tracks.iter().flat_map(|track| &track.channels);
+
+    let loudest = |level: fn(&Channel) -> Decibel| {
+        channels().map(level).max_by(|a, b| a.get_value().total_cmp(&b.get_value()))
+    };
+
+    let mut rms_meter = RootMeanSquareMeter::default();
+    for channel in channels() {
+        rms_meter.merge(&channel.rms_meter);
+    }
+
+    let mut correlation_meter: Option<StereoCorrelationMeter> = None;
+    for pair in tracks.iter().flat_map(|track| &track.channel_pairs) {
+        if pair.left == ChannelLabel::FrontLeft && pair.right == ChannelLabel::FrontRight {
+            correlation_meter.get_or_insert_with(StereoCorrelationMeter::default).merge(&pair.correlation_meter);
+        }
+    }
+
+    let blocks: Vec<f64> = tracks.iter().flat_map(|track| track.loudness_blocks.iter().copied()).collect();
+    let integrated_loudness = Loudness::new(gated_loudness(&blocks));
+
+    let peak = loudest(Channel::peak).unwrap_or(Decibel::new(0.0));
+    let true_peak = loudest(Channel::true_peak).unwrap_or(Decibel::new(0.0));
+    let replay_gain = match replay_gain_peak {
+        ReplayGainPeak::Sample => ReplayGain::new(integrated_loudness, peak),
+        ReplayGainPeak::True => ReplayGain::new(integrated_loudness, true_peak),
+    };
+
+    Ok(Album {
+        tracks: tracks.len(),
+        duration: tracks.iter().map(|track| track.duration).sum(),
+        dr: DynamicRange::mean(tracks.iter().map(|track| track.dr())),
+        peak,
+        true_peak,
+        rms: Decibel::new(rms_meter.value()?),
+        correlation: correlation_meter.map(|meter| meter.value()),
+        integrated_loudness,
+        replay_gain,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::decoders::flac::test_utils::analyse_samples;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn tone(frames: u32, amplitude: f64, phase: f64) -> Vec<i32> {
+        (0..frames)
+            .flat_map(|index| {
+                let left = (f64::from(index) * 0.05).sin() * amplitude;
+                let right = (f64::from(index) * 0.05 + phase).sin() * amplitude;
+                [left as i32, right as i32]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn album_levels_match_concatenated_program() {
+        let loud = tone(30000, 20000.0, 0.3);
+        let quiet = tone(50000, 2000.0, 2.0);
+        let whole: Vec<i32> = loud.iter().chain(&quiet).copied().collect();
+
+        let tracks = [analyse_samples(&loud, 2, 44100, 16), analyse_samples(&quiet, 2, 44100, 16)];
+        let album = album_from_tracks(&[&tracks[0], &tracks[1]], ReplayGainPeak::Sample).unwrap();
+        let concatenated = analyse_samples(&whole, 2, 44100, 16);
+
+        let mut expected_rms = RootMeanSquareMeter::default();
+        for channel in &concatenated.channels {
+            expected_rms.merge(&channel.rms_meter);
+        }
+
+        assert_eq!(album.tracks, 2);
+        assert!((album.rms.get_value() - Decibel::new(expected_rms.value().unwrap()).get_value()).abs() < 1e-9);
+        assert!((album.correlation.unwrap() - concatenated.channel_pairs[0].correlation).abs() < 1e-9);
+        assert!((album.peak.get_value() - concatenated.channels[0].peak().get_value()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn album_gain_matches_concatenated_program() {
+        let loud = tone(220_500, 16000.0, 0.3);
+        let quiet = tone(220_500, 8000.0, 0.3);
+        let whole: Vec<i32> = loud.iter().chain(&quiet).copied().collect();
+
+        let tracks = [analyse_samples(&loud, 2, 44100, 16), analyse_samples(&quiet, 2, 44100, 16)];
+        let album = album_from_tracks(&[&tracks[0], &tracks[1]], ReplayGainPeak::Sample).unwrap();
+        let album_gain = album.replay_gain.unwrap();
+        let program_gain = analyse_samples(&whole, 2, 44100, 16).replay_gain.unwrap();
+
+        assert!((album_gain.gain() - program_gain.gain()).abs() < 0.05);
+        assert!((album_gain.peak() - program_gain.peak()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn album_peak_follows_the_requested_peak() {
+        let track = analyse_samples(&tone(30000, 20000.0, 0.3), 2, 44100, 16);
+        let album = album_from_tracks(&[&track, &track], ReplayGainPeak::True).unwrap();
+
+        assert_eq!(album.replay_gain.unwrap().peak_tag(), track.replay_gain_with(ReplayGainPeak::True).unwrap().peak_tag());
+    }
+
+    #[test]
+    fn empty_album() {
+        assert!(album_from_tracks(&[], ReplayGainPeak::Sample).is_err());
+    }
+}
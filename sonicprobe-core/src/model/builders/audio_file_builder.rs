@@ -1,49 +1,199 @@
-use std::fs::File;
-use std::sync::Arc;
-use std::thread;
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
 
-use claxon::FlacReader;
-
-use crate::dsp::analysis::bit_depth::calculate_true_depth;
-use crate::dsp::analysis::stereo_correlation::calculate_stereo_correlation;
+use crate::decoders::{AudioDecoder, SampleFormat};
+use crate::dsp::analysis::bit_depth::TrueDepthMeter;
+use crate::dsp::analysis::lossy_transcode::LossyTranscodeMeter;
+use crate::dsp::analysis::loudness::{ChannelStepMeter, LoudnessMeter};
+use crate::dsp::analysis::spectrum::SpectrumMeter;
+use crate::dsp::analysis::stereo_correlation::StereoCorrelationMeter;
 use crate::model::audio_file::AudioFile;
+use crate::model::bit_depth::BitDepth;
 use crate::model::builders::channel_builder::ChannelBuilder;
-use crate::model::builders::stereo_signal_builder::stereo_signal_from_flac;
+use crate::model::channel::{Channel, ChannelPair};
+use crate::model::channel_layout::{correlation_pairs, ChannelLabel};
+use crate::model::decibel::Decibel;
+use crate::model::dynamic_range::DynamicRange;
+use crate::model::frequency::Frequency;
+use crate::model::lossy_transcode::LossyTranscode;
+use crate::model::loudness::{Loudness, LoudnessRange, PeakToLoudness};
+use crate::model::replay_gain::ReplayGainPeak;
 use crate::model::sonicprobe_error::SonicProbeError;
+use crate::model::spectrum::{Spectrum, SpectrumSettings};
+
+// Blocks queued per channel worker before the decoder waits, bounding memory use
+const CHANNEL_QUEUE_DEPTH: usize = 16;
+
+struct ChannelWorker {
+    sender: SyncSender<Vec<f64>>,
+    handle: JoinHandle<Result<(Channel, ChannelStepMeter, Spectrum), SonicProbeError>>,
+}
+
+impl ChannelWorker {
+    fn spawn(label: ChannelLabel, sample_rate: Frequency, spectrum_settings: SpectrumSettings) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<Vec<f64>>(CHANNEL_QUEUE_DEPTH);
+        let handle = thread::spawn(move || {
+            let mut builder = ChannelBuilder::new(label, sample_rate);
+            let mut spectrum_meter = SpectrumMeter::new(label, sample_rate, spectrum_settings);
+            for block in receiver {
+                builder.push_samples(&block)?;
+                spectrum_meter.push_samples(&block)?;
+            }
+            let (channel, step_meter) = builder.build_with_steps()?;
+            Ok((channel, step_meter, spectrum_meter.value()))
+        });
+
+        Self { sender, handle }
+    }
+}
+
+struct TranscodeWorker {
+    sender: SyncSender<Vec<f64>>,
+    handle: JoinHandle<Result<LossyTranscode, SonicProbeError>>,
+}
+
+impl TranscodeWorker {
+    fn spawn(sample_rate: Frequency) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<Vec<f64>>(CHANNEL_QUEUE_DEPTH);
+        let handle = thread::spawn(move || {
+            let mut meter = LossyTranscodeMeter::new(sample_rate);
+            for block in receiver {
+                meter.push_samples(&block)?;
+            }
+            Ok(meter.value())
+        });
+
+        Self { sender, handle }
+    }
+}
+
+/// # Errors
+///
+/// Fails when the stream has no channels or an unsupported sample rate or bit depth, when a
+/// block cannot be decoded, or when a meter fails on the decoded samples.
+pub fn audio_file_form_stream<D: AudioDecoder + ?Sized>(stream: &mut D) -> Result<AudioFile, SonicProbeError> {
+    audio_file_form_stream_with(stream, SpectrumSettings::default())
+}
+
+/// # Errors
+///
+/// Fails for the same reasons as [`audio_file_form_stream`].
+pub fn audio_file_form_stream_with<D: AudioDecoder + ?Sized>(
+    stream: &mut D,
+    spectrum_settings: SpectrumSettings,
+) -> Result<AudioFile, SonicProbeError> {
+    let infos = stream.stream_info();
+
+    if infos.channels == 0 {
+        return Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "stream has no channels".to_owned(),
+        });
+    }
+
+    let sample_rate = Frequency::new(infos.sample_rate)?;
+    let depth = match infos.sample_format {
+        SampleFormat::Integer => BitDepth::new(infos.bits_per_sample)?,
+        SampleFormat::Float => BitDepth::new_float(infos.bits_per_sample)?,
+    };
+    let layout = stream.channel_layout();
+    let metadata = stream.metadata();
 
-pub fn audio_file_form_stream(stream: FlacReader<File>) -> Result<AudioFile, SonicProbeError> {
-    let stereo_signal = stereo_signal_from_flac(stream)?;
+    let workers: Vec<ChannelWorker> =
+        layout.iter().map(|&label| ChannelWorker::spawn(label, sample_rate, spectrum_settings)).collect();
 
-    let left_thread_handle = thread::spawn({
-        let left_channel = Arc::clone(&stereo_signal.left);
-        let sample_rate = stereo_signal.sample_rate;
-        move || ChannelBuilder::new(&left_channel, sample_rate).build()
-    });
+    let pairs = correlation_pairs(&layout);
+    let mut correlation_meters = vec![StereoCorrelationMeter::default(); pairs.len()];
+    let mut true_depth_meter = TrueDepthMeter::new(depth);
+    let transcode_worker = TranscodeWorker::spawn(sample_rate);
+    #[allow(clippy::cast_precision_loss)]
+    let mixdown_gain = 1.0 / workers.len() as f64;
+    let mut samples_per_channel = 0;
 
-    let right_thread_handle = thread::spawn({
-        let right_channel = Arc::clone(&stereo_signal.right);
-        let sample_rate = stereo_signal.sample_rate;
-        move || ChannelBuilder::new(&right_channel, sample_rate).build()
-    });
+    while let Some(block) = stream.next_block()? {
+        let frames = block.len() / workers.len();
+        let mut channels: Vec<Vec<f64>> = (0..workers.len()).map(|_| Vec::with_capacity(frames)).collect();
+        let mut mixdown = Vec::with_capacity(frames);
 
-    let true_bit_depth = calculate_true_depth(&stereo_signal)?;
+        for frame in block.chunks_exact(workers.len()) {
+            for (channel, sample) in channels.iter_mut().zip(frame) {
+                true_depth_meter.push_sample(*sample)?;
+                channel.push(*sample);
+            }
 
-    let samples_per_channel = stereo_signal.samples_per_channel();
+            for (meter, (left, right)) in correlation_meters.iter_mut().zip(&pairs) {
+                meter.push_frame(frame[*left], frame[*right]);
+            }
 
-    let left = left_thread_handle.join()??;
-    let right = right_thread_handle.join()??;
+            // The lowpass of a lossy codec applies to every channel
+            mixdown.push(frame.iter().sum::<f64>() * mixdown_gain);
+        }
 
-    let stereo_correlation = calculate_stereo_correlation(&stereo_signal.left, &stereo_signal.right);
+        samples_per_channel += frames;
 
-    Ok(AudioFile {
-        left,
-        right,
-        channels: 2,
-        stereo_correlation,
-        true_depth: true_bit_depth,
-        depth: stereo_signal.depth,
-        sample_rate: stereo_signal.sample_rate,
+        // A worker only hangs up after failing, its error is reported on join
+        for (worker, channel) in workers.iter().zip(channels) {
+            if worker.sender.send(channel).is_err() {
+                break;
+            }
+        }
+        let _ = transcode_worker.sender.send(mixdown);
+    }
+
+    drop(transcode_worker.sender);
+    let lossy_transcode = transcode_worker.handle.join()??;
+
+    let mut loudness_meter = LoudnessMeter::new(sample_rate);
+    let mut spectrum = Vec::with_capacity(workers.len());
+    let channels = workers
+        .into_iter()
+        .map(|worker| {
+            drop(worker.sender);
+            let (channel, step_meter, channel_spectrum) = worker.handle.join()??;
+            loudness_meter.add_channel(channel.label(), &step_meter);
+            spectrum.push(channel_spectrum);
+            Ok(channel)
+        })
+        .collect::<Result<Vec<Channel>, SonicProbeError>>()?;
+
+    let channel_pairs = pairs
+        .into_iter()
+        .zip(correlation_meters)
+        .map(|((left, right), meter)| ChannelPair {
+            left: layout[left],
+            right: layout[right],
+            correlation: meter.value(),
+            rms_balance: channels[left].rms() - channels[right].rms(),
+            correlation_meter: meter,
+        })
+        .collect();
+
+    let dr = DynamicRange::from_channels(channels.iter().map(Channel::dr_value));
+    let integrated_loudness = Loudness::new(loudness_meter.integrated_loudness());
+    let true_peak = channels.iter().map(Channel::true_peak).max_by(|a, b| a.get_value().total_cmp(&b.get_value()));
+    let peak_to_loudness = PeakToLoudness::new(true_peak.unwrap_or(Decibel::new(0.0)), integrated_loudness, &loudness_meter.psr());
+    let mut audio_file = AudioFile {
+        channels,
+        channel_pairs,
+        true_depth: true_depth_meter.value(),
+        lossy_transcode,
+        dr,
+        integrated_loudness,
+        loudness_range: LoudnessRange::new(loudness_meter.loudness_range()),
+        max_short_term_loudness: Loudness::new(loudness_meter.max_short_term_loudness()),
+        peak_to_loudness,
+        spectrum,
+        loudness_series: loudness_meter.series(),
+        loudness_blocks: loudness_meter.blocks(),
+        replay_gain: None,
+        compliance: Vec::new(),
+        depth,
+        sample_rate,
         samples_per_channel,
-        duration: samples_per_channel / stereo_signal.sample_rate.to_hz(),
-    })
+        duration: samples_per_channel / sample_rate.to_hz(),
+        metadata,
+    };
+    audio_file.replay_gain = audio_file.replay_gain_with(ReplayGainPeak::Sample);
+
+    Ok(audio_file)
 }
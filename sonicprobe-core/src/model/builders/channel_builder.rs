@@ -1,78 +1,186 @@
-use std::sync::Arc;
-
 use crate::{
-    analysis::{dynamic_range::DynamicRangeMeter, peak::update_peak_value}, dsp::{
+    analysis::{dynamic_range::DynamicRangeMeter, peak::update_peak_value, rms_range::RmsRangeMeter}, dsp::{
         analysis::{
-            clipping::update_clipping_count, dc_offset::calculate_dc_offset,
-            root_mean_square::compute_root_mean_square,
-            zero_crossing_rate::calculate_zero_crossing_rate,
+            clipping::update_clipping_count, dc_offset::DcOffsetMeter,
+            loudness::ChannelStepMeter,
+            root_mean_square::RootMeanSquareMeter,
+            zero_crossing_rate::ZeroCrossingRateMeter,
         },
-        upsample_chain,
-    }, model::{Signal, channel::Channel, decibel::Decibel, dynamic_range::DynamicRange, frequency::Frequency, sonicprobe_error::SonicProbeError}
+        TruePeakMeter,
+    }, model::{channel::Channel, channel_layout::ChannelLabel, decibel::Decibel, dynamic_range::DynamicRange, frequency::Frequency, sonicprobe_error::SonicProbeError}
 };
 
-#[repr(C)]
 #[allow(clippy::module_name_repetitions)]
 pub struct ChannelBuilder {
-    signal: Signal,
-    duration: f64,
-    sample_rate: Frequency,
+    label: ChannelLabel,
+    samples_count: u64,
+    clipping_samples_count: u64,
+    peak: f64,
+    dr_meter: DynamicRangeMeter,
+    rms_range_meter: RmsRangeMeter,
+    dc_offset_meter: DcOffsetMeter,
+    rms_meter: RootMeanSquareMeter,
+    zcr_meter: ZeroCrossingRateMeter,
+    true_peak_meter: TruePeakMeter,
+    step_meter: ChannelStepMeter,
 }
 
 impl ChannelBuilder {
-    #[allow(clippy::cast_precision_loss)]
-    pub fn new(signal: &Signal, sample_rate: Frequency) -> Self {
-        let duration = signal.len() as f64 / f64::from(sample_rate);
-
+    #[must_use]
+    pub fn new(label: ChannelLabel, sample_rate: Frequency) -> Self {
         Self {
-            signal: Arc::clone(signal),
-            sample_rate,
-            duration,
+            label,
+            samples_count: 0,
+            clipping_samples_count: 0,
+            peak: f64::MIN,
+            dr_meter: DynamicRangeMeter::new(sample_rate),
+            rms_range_meter: RmsRangeMeter::new(&sample_rate),
+            dc_offset_meter: DcOffsetMeter::default(),
+            rms_meter: RootMeanSquareMeter::default(),
+            zcr_meter: ZeroCrossingRateMeter::new(sample_rate),
+            true_peak_meter: TruePeakMeter::new(sample_rate),
+            step_meter: ChannelStepMeter::new(sample_rate),
         }
     }
 
+    /// # Errors
+    ///
+    /// Fails when the RMS of a completed RMS range chunk cannot be computed.
+    pub fn push_samples(&mut self, samples: &[f64]) -> Result<(), SonicProbeError> {
+        for sample in samples {
+            if let Some(result) = update_clipping_count(&self.clipping_samples_count, sample) {
+                self.clipping_samples_count = result;
+            }
+
+            if let Some(result) = update_peak_value(&self.peak, &sample.abs()) {
+                self.peak = result;
+            }
+
+            self.dr_meter.push_sample(*sample);
+            self.rms_range_meter.push_sample(sample)?;
+            self.dc_offset_meter.push_sample(*sample);
+            self.rms_meter.push_sample(*sample);
+            self.zcr_meter.push_sample(*sample);
+            let true_peak = self.true_peak_meter.push_sample(*sample);
+            self.step_meter.push_sample(*sample, true_peak);
+        }
+
+        self.samples_count += samples.len() as u64;
+
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Fails when no sample was pushed or the sample count cannot be represented exactly as an `f64`.
     pub fn build(self) -> Result<Channel, SonicProbeError> {
-        from_samples(&self)
+        self.build_with_steps().map(|(channel, _)| channel)
+    }
+
+    /// The per-step measurements feed the program-wide loudness, which needs every channel
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`ChannelBuilder::build`].
+    pub fn build_with_steps(mut self) -> Result<(Channel, ChannelStepMeter), SonicProbeError> {
+        self.true_peak_meter.flush();
+        let (true_peak, true_clipping_samples_count) = self.true_peak_meter.value();
+        let dr_value = self.dr_meter.value();
+
+        let channel = Channel {
+            label: self.label,
+            rms: Decibel::new(self.rms_meter.value()?),
+            rms_meter: self.rms_meter,
+            peak: Decibel::new(self.peak),
+            true_peak,
+            samples_count: self.samples_count,
+            zero_crossing_rate: self.zcr_meter.value(),
+            dc_offset: self.dc_offset_meter.value()?,
+            clipping_samples_count: self.clipping_samples_count,
+            true_clipping_samples_count,
+            dr: DynamicRange::rounded(dr_value),
+            dr_value,
+            rms_range: self.rms_range_meter.value(),
+        };
+
+        Ok((channel, self.step_meter))
     }
 }
 
-pub fn from_samples(builder: &ChannelBuilder) -> Result<Channel, SonicProbeError> {
-    let samples = &builder.signal;
-        
-    let mut clipping_samples_count = 0u64;
-    let mut peak = f64::MIN;
-    let mut dr_meter = DynamicRangeMeter::new(&samples.len(), &builder.sample_rate);
-
-    for sample in samples.iter() {
-        match update_clipping_count(&clipping_samples_count, sample) {
-            Some(result) => clipping_samples_count = result,
-            None => {}
-        }
+/// # Errors
+///
+/// Fails when `samples` is empty, or for the same reasons as [`ChannelBuilder::push_samples`].
+pub fn from_samples(samples: &[f64], label: ChannelLabel, sample_rate: Frequency) -> Result<Channel, SonicProbeError> {
+    let mut builder = ChannelBuilder::new(label, sample_rate);
+    builder.push_samples(samples)?;
+    builder.build()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::decoders::flac::test_utils::analyse_samples;
+    use crate::model::bit_depth::DepthUsage;
 
-        match update_peak_value(&peak, &sample.abs()) {
-            Some(result) => peak = result,
-            None => {}
+    #[test]
+    fn blocks_match_single_pass() {
+        let samples: Vec<f64> = (0..200_000).map(|i| (f64::from(i) * 0.013).sin() * 0.7).collect();
+        let expected = from_samples(&samples, ChannelLabel::FrontLeft, Frequency::CD_QUALITY).unwrap();
+
+        let mut builder = ChannelBuilder::new(ChannelLabel::FrontLeft, Frequency::CD_QUALITY);
+        for block in samples.chunks(4093) {
+            builder.push_samples(block).unwrap();
         }
+        let streamed = builder.build().unwrap();
+
+        assert_eq!(serde_json::to_string(&streamed).unwrap(), serde_json::to_string(&expected).unwrap());
+    }
 
-        dr_meter.push_sample(sample)?
+    // 18 s of 16-bit stereo with level changes, a DC offset and full scale clicks
+    #[allow(clippy::cast_sign_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    fn baseline_fixture() -> Vec<i32> {
+        const LEVELS: [f64; 18] = [0.2, 0.5, 0.9, 0.3, 0.05, 0.7, 0.6, 0.1, 0.8, 0.4, 0.25, 0.95, 0.15, 0.55, 0.35, 0.75, 0.45, 0.65];
+
+        (0..44100 * 18)
+            .flat_map(|index: i32| {
+                let level = LEVELS[(index / 44100) as usize];
+                let time = f64::from(index);
+                let left = level * (time * 0.031).sin() + 0.02 * (time * 0.0007).sin();
+                let right = level * 0.8 * (time * 0.113 + 1.0).sin() - 0.01;
+                let click = if index % 7919 == 0 { [32767, -32767] } else { [0, 0] };
+
+                [(left * 32000.0).round() as i32 + 200, (right * 32000.0).round() as i32]
+                    .into_iter()
+                    .zip(click)
+                    .map(|(sample, click)| if click == 0 { sample } else { click })
+            })
+            .collect()
     }
 
-    
-    let dc_offset = calculate_dc_offset(samples)?;
-    let rms = Decibel::new(compute_root_mean_square(samples)?);
-    let zcr = calculate_zero_crossing_rate(samples, builder.sample_rate);
-
-    let (true_peak, true_clipping_samples_count) = upsample_chain(samples, builder.sample_rate)?;
-
-    Ok(Channel {
-        rms,
-        peak: Decibel::new(peak),
-        true_peak,
-        samples_count: builder.signal.len() as u64,
-        zero_crossing_rate: zcr,
-        dc_offset,
-        clipping_samples_count,
-        true_clipping_samples_count,
-        dr: DynamicRange::from(dr_meter.get_dr_value()),
-    })
-}
\ No newline at end of file
+    // Values captured on the same fixture from the whole-file path this streaming path replaced
+    // (read_audio_signal and the meters of the baseline release). True peak is left out, its
+    // interpolation filter has changed since
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn matches_the_whole_file_baseline() {
+        let file = analyse_samples(&baseline_fixture(), 2, 44100, 16);
+        let expected = [
+            (0.006_245_876_127_777_017, -8.392_515_339_735_855, 4.225_915_149_377_746, 417),
+            (-0.009_887_430_989_934_646, -10.331_165_064_891_374, 4.224_393_552_801_844, 1507),
+        ];
+
+        for (channel, (dc_offset, rms, rms_range, zero_crossing_rate)) in file.channels.iter().zip(expected) {
+            assert_eq!(channel.samples_count, 793_800);
+            assert_eq!(channel.dc_offset(), dc_offset);
+            assert_eq!(channel.peak().get_value(), 0.0);
+            assert_eq!(channel.rms().get_value(), rms);
+            assert_eq!(channel.rms_range().get_value(), rms_range);
+            assert_eq!(channel.clipping_samples_count, 101);
+            assert_eq!(channel.zero_crossing_rate(), zero_crossing_rate);
+        }
+        assert_eq!(file.channel_pairs[0].correlation, -0.001_874_361_538_429_013_6);
+        assert_eq!(file.true_depth, DepthUsage::Integer(16));
+    }
+}
@@ -0,0 +1,189 @@
+use serde::{Serialize, Serializer};
+
+// Speaker positions in WAVE_FORMAT_EXTENSIBLE channel mask bit order
+const MASK_ORDER: [ChannelLabel; 18] = [
+    ChannelLabel::FrontLeft,
+    ChannelLabel::FrontRight,
+    ChannelLabel::FrontCenter,
+    ChannelLabel::LowFrequency,
+    ChannelLabel::BackLeft,
+    ChannelLabel::BackRight,
+    ChannelLabel::FrontLeftOfCenter,
+    ChannelLabel::FrontRightOfCenter,
+    ChannelLabel::BackCenter,
+    ChannelLabel::SideLeft,
+    ChannelLabel::SideRight,
+    ChannelLabel::TopCenter,
+    ChannelLabel::TopFrontLeft,
+    ChannelLabel::TopFrontCenter,
+    ChannelLabel::TopFrontRight,
+    ChannelLabel::TopBackLeft,
+    ChannelLabel::TopBackCenter,
+    ChannelLabel::TopBackRight,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChannelLabel {
+    Mono,
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+    LowFrequency,
+    BackLeft,
+    BackRight,
+    FrontLeftOfCenter,
+    FrontRightOfCenter,
+    BackCenter,
+    SideLeft,
+    SideRight,
+    TopCenter,
+    TopFrontLeft,
+    TopFrontCenter,
+    TopFrontRight,
+    TopBackLeft,
+    TopBackCenter,
+    TopBackRight,
+    Discrete(u16),
+}
+
+impl Serialize for ChannelLabel {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.short_name())
+    }
+}
+
+impl ChannelLabel {
+    #[must_use]
+    pub fn short_name(self) -> String {
+        let name = match self {
+            Self::Mono => "M",
+            Self::FrontLeft => "L",
+            Self::FrontRight => "R",
+            Self::FrontCenter => "C",
+            Self::LowFrequency => "LFE",
+            Self::BackLeft => "Lrs",
+            Self::BackRight => "Rrs",
+            Self::FrontLeftOfCenter => "Lc",
+            Self::FrontRightOfCenter => "Rc",
+            Self::BackCenter => "Cs",
+            Self::SideLeft => "Ls",
+            Self::SideRight => "Rs",
+            Self::TopCenter => "Tc",
+            Self::TopFrontLeft => "Tfl",
+            Self::TopFrontCenter => "Tfc",
+            Self::TopFrontRight => "Tfr",
+            Self::TopBackLeft => "Tbl",
+            Self::TopBackCenter => "Tbc",
+            Self::TopBackRight => "Tbr",
+            Self::Discrete(index) => return format!("Ch{}", index + 1),
+        };
+
+        name.to_owned()
+    }
+}
+
+// Layout used when the container says nothing about speaker positions
+pub fn default_layout(channels: u16) -> Vec<ChannelLabel> {
+    match channels {
+        1 => vec![ChannelLabel::Mono],
+        2 => vec![ChannelLabel::FrontLeft, ChannelLabel::FrontRight],
+        _ => (0..channels).map(ChannelLabel::Discrete).collect(),
+    }
+}
+
+#[must_use]
+pub fn layout_from_mask(mask: u32, channels: u16) -> Vec<ChannelLabel> {
+    let labels: Vec<ChannelLabel> = MASK_ORDER
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| mask & (1 << bit) != 0)
+        .map(|(_, label)| *label)
+        .collect();
+
+    if labels.len() == usize::from(channels) {
+        labels
+    } else {
+        default_layout(channels)
+    }
+}
+
+// FLAC defines the speaker assignment implicitly from the channel count
+#[must_use]
+pub fn flac_layout(channels: u16) -> Vec<ChannelLabel> {
+    use ChannelLabel::{
+        BackCenter, BackLeft, BackRight, FrontCenter, FrontLeft, FrontRight, LowFrequency, SideLeft, SideRight,
+    };
+
+    match channels {
+        3 => vec![FrontLeft, FrontRight, FrontCenter],
+        4 => vec![FrontLeft, FrontRight, BackLeft, BackRight],
+        5 => vec![FrontLeft, FrontRight, FrontCenter, SideLeft, SideRight],
+        6 => vec![FrontLeft, FrontRight, FrontCenter, LowFrequency, SideLeft, SideRight],
+        7 => vec![FrontLeft, FrontRight, FrontCenter, LowFrequency, BackCenter, SideLeft, SideRight],
+        8 => vec![FrontLeft, FrontRight, FrontCenter, LowFrequency, BackLeft, BackRight, SideLeft, SideRight],
+        _ => default_layout(channels),
+    }
+}
+
+// Front L/R and the surround pair, using the rear speakers when no side pair exists
+#[must_use]
+pub fn correlation_pairs(layout: &[ChannelLabel]) -> Vec<(usize, usize)> {
+    let position = |label: ChannelLabel| layout.iter().position(|candidate| *candidate == label);
+
+    let front = position(ChannelLabel::FrontLeft).zip(position(ChannelLabel::FrontRight));
+    let surround = position(ChannelLabel::SideLeft)
+        .zip(position(ChannelLabel::SideRight))
+        .or_else(|| position(ChannelLabel::BackLeft).zip(position(ChannelLabel::BackRight)));
+
+    front.into_iter().chain(surround).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_5_1() {
+        let layout = layout_from_mask(0x60F, 6);
+        let names: Vec<String> = layout.iter().map(|label| label.short_name()).collect();
+
+        assert_eq!(names, ["L", "R", "C", "LFE", "Ls", "Rs"]);
+    }
+
+    #[test]
+    fn mask_not_matching_channel_count() {
+        assert_eq!(layout_from_mask(0x3, 4), default_layout(4));
+        assert_eq!(layout_from_mask(0, 2), default_layout(2));
+    }
+
+    #[test]
+    fn flac_7_1() {
+        let names: Vec<String> = flac_layout(8).iter().map(|label| label.short_name()).collect();
+
+        assert_eq!(names, ["L", "R", "C", "LFE", "Lrs", "Rrs", "Ls", "Rs"]);
+    }
+
+    #[test]
+    fn ambisonic_channels_are_discrete() {
+        let names: Vec<String> = flac_layout(16).iter().map(|label| label.short_name()).collect();
+
+        assert_eq!(names.first().map(String::as_str), Some("Ch1"));
+        assert_eq!(names.last().map(String::as_str), Some("Ch16"));
+    }
+
+    #[test]
+    fn pairs_for_surround_layouts() {
+        assert_eq!(correlation_pairs(&flac_layout(6)), [(0, 1), (4, 5)]);
+        assert_eq!(correlation_pairs(&flac_layout(8)), [(0, 1), (6, 7)]);
+        assert_eq!(correlation_pairs(&layout_from_mask(0x3F, 6)), [(0, 1), (4, 5)]);
+    }
+
+    #[test]
+    fn pairs_for_mono_and_stereo() {
+        assert!(correlation_pairs(&default_layout(1)).is_empty());
+        assert_eq!(correlation_pairs(&default_layout(2)), [(0, 1)]);
+    }
+}
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::audio_file::AudioFile;
+
+const DEFAULT_TOLERANCE: f64 = 1.0;
+const NOT_DIALOGUE_GATED: &str = "not dialogue-gated";
+
+// Delivery target, every limit left out is not checked. `note` tells how the
+// measurement departs from the specification the profile is named after
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceProfile {
+    pub name: String,
+    pub integrated: Option<f64>,
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f64,
+    pub true_peak: Option<f64>,
+    pub loudness_range: Option<f64>,
+    pub short_term: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComplianceMetric {
+    Integrated,
+    TruePeak,
+    LoudnessRange,
+    ShortTerm,
+}
+
+// `margin` is the distance to the limit, negative when the check fails
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ComplianceCheck {
+    pub metric: ComplianceMetric,
+    pub measured: f64,
+    pub limit: f64,
+    pub margin: f64,
+    pub passed: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ComplianceReport {
+    pub profile: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub checks: Vec<ComplianceCheck>,
+    pub passed: bool,
+}
+
+const fn default_tolerance() -> f64 {
+    DEFAULT_TOLERANCE
+}
+
+impl ComplianceProfile {
+    fn new(name: &str, integrated: f64, tolerance: f64, true_peak: f64) -> Self {
+        Self {
+            name: name.to_owned(),
+            integrated: Some(integrated),
+            tolerance,
+            true_peak: Some(true_peak),
+            loudness_range: None,
+            short_term: None,
+            note: None,
+        }
+    }
+
+    // Netflix measures dialogue-gated loudness, this profile holds the whole program to its
+    // target and is named and noted so that it is not mistaken for the Netflix measurement
+    #[must_use]
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::new("spotify", -14.0, 1.0, -1.0),
+            Self::new("youtube", -14.0, 1.0, -1.0),
+            Self::new("apple-music", -16.0, 1.0, -1.0),
+            Self::new("ebu-r128", -23.0, 0.5, -1.0),
+            Self::new("atsc-a85", -24.0, 2.0, -2.0),
+            Self { note: Some(NOT_DIALOGUE_GATED.to_owned()), ..Self::new("netflix-program", -27.0, 2.0, -2.0) },
+        ]
+    }
+
+    pub fn check(&self, file: &AudioFile) -> ComplianceReport {
+        let true_peak = file
+            .channels
+            .iter()
+            .map(|channel| channel.true_peak().get_value())
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        let mut checks = Vec::new();
+
+        if let Some(target) = self.integrated {
+            let measured = file.integrated_loudness.get_value();
+            checks.push(ComplianceCheck::within(ComplianceMetric::Integrated, measured, target, self.tolerance));
+        }
+
+        let maximums = [
+            (ComplianceMetric::TruePeak, true_peak, self.true_peak),
+            (ComplianceMetric::LoudnessRange, file.loudness_range.get_value(), self.loudness_range),
+            (ComplianceMetric::ShortTerm, file.max_short_term_loudness.get_value(), self.short_term),
+        ];
+
+        for (metric, measured, limit) in maximums {
+            if let Some(limit) = limit {
+                checks.push(ComplianceCheck::at_most(metric, measured, limit));
+            }
+        }
+
+        ComplianceReport {
+            profile: self.name.clone(),
+            note: self.note.clone(),
+            passed: checks.iter().all(|check| check.passed),
+            checks,
+        }
+    }
+}
+
+impl ComplianceCheck {
+    // A program too quiet to measure never meets a loudness target
+    fn within(metric: ComplianceMetric, measured: f64, target: f64, tolerance: f64) -> Self {
+        let margin = if measured.is_finite() { tolerance - (measured - target).abs() } else { f64::NEG_INFINITY };
+
+        Self { metric, measured, limit: target, margin, passed: margin >= 0.0 }
+    }
+
+    fn at_most(metric: ComplianceMetric, measured: f64, limit: f64) -> Self {
+        let margin = limit - measured;
+
+        Self { metric, measured, limit, margin, passed: margin >= 0.0 }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use crate::decoders::flac::test_utils::analyse_samples;
+
+    fn profile(name: &str) -> ComplianceProfile {
+        ComplianceProfile::builtin().into_iter().find(|profile| profile.name == name).unwrap()
+    }
+
+    #[test]
+    fn target_with_tolerance() {
+        let check = ComplianceCheck::within(ComplianceMetric::Integrated, -23.3, -23.0, 0.5);
+        assert!(check.passed);
+        assert!((check.margin - 0.2).abs() < 1e-9);
+
+        assert!(!ComplianceCheck::within(ComplianceMetric::Integrated, -22.4, -23.0, 0.5).passed);
+        assert!(!ComplianceCheck::within(ComplianceMetric::Integrated, f64::NEG_INFINITY, -23.0, 0.5).passed);
+    }
+
+    #[test]
+    fn maximum_limit() {
+        let check = ComplianceCheck::at_most(ComplianceMetric::TruePeak, -0.5, -1.0);
+        assert!(!check.passed);
+        assert_eq!(check.margin, -0.5);
+
+        assert!(ComplianceCheck::at_most(ComplianceMetric::TruePeak, -1.0, -1.0).passed);
+    }
+
+    #[test]
+    fn builtin_profiles() {
+        let r128 = profile("ebu-r128");
+        assert_eq!(r128.integrated, Some(-23.0));
+        assert_eq!(r128.true_peak, Some(-1.0));
+
+        assert_eq!(profile("atsc-a85").true_peak, Some(-2.0));
+        assert_eq!(profile("apple-music").integrated, Some(-16.0));
+        assert_eq!(profile("netflix-program").note.as_deref(), Some("not dialogue-gated"));
+        assert_eq!(profile("spotify").note, None);
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn every_limit_is_checked() {
+        let samples: Vec<i32> = (0..441_000).map(|index| ((f64::from(index) * 0.05).sin() * 16000.0) as i32).collect();
+        let file = analyse_samples(&samples, 2, 44100, 16);
+
+        let strict = ComplianceProfile {
+            loudness_range: Some(1.0),
+            short_term: Some(-30.0),
+            ..profile("ebu-r128")
+        };
+        let report = strict.check(&file);
+        let metrics: Vec<ComplianceMetric> = report.checks.iter().map(|check| check.metric).collect();
+
+        assert_eq!(
+            metrics,
+            [ComplianceMetric::Integrated, ComplianceMetric::TruePeak, ComplianceMetric::LoudnessRange, ComplianceMetric::ShortTerm]
+        );
+        assert!(!report.passed);
+        assert!(report.checks[2].passed);
+        assert!(!report.checks[3].passed);
+    }
+
+    #[test]
+    fn note_is_reported() {
+        let file = analyse_samples(&vec![0; 8820], 2, 44100, 16);
+
+        let netflix = serde_json::to_string(&profile("netflix-program").check(&file)).unwrap();
+        let r128 = serde_json::to_string(&profile("ebu-r128").check(&file)).unwrap();
+
+        assert!(netflix.starts_with(r#"{"profile":"netflix-program","note":"not dialogue-gated","#));
+        assert!(!r128.contains("note"));
+    }
+
+    #[test]
+    fn user_profile_defaults() {
+        let profile: ComplianceProfile =
+            serde_json::from_str(r#"{ "name": "podcast", "integrated": -16.0, "short_term": -12.0 }"#).unwrap();
+
+        assert_eq!(profile.tolerance, DEFAULT_TOLERANCE);
+        assert_eq!(profile.true_peak, None);
+        assert_eq!(profile.short_term, Some(-12.0));
+        assert_eq!(profile.note, None);
+    }
+}
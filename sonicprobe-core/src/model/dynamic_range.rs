@@ -6,18 +6,51 @@ use crate::model::decibel::Decibel;
 pub struct DynamicRange(i64);
 
 impl From<Decibel> for DynamicRange {
-    #[allow(clippy::cast_possible_truncation)]
     fn from(obj: Decibel) -> Self {
-        let value = obj.get_value().round().abs() as i64;
-        Self(value)
+        Self::rounded(obj)
     }
 }
 
 impl DynamicRange {
+        // Rounded mean, as the DR database combines channels into a track and tracks into an album
+        #[allow(clippy::cast_precision_loss)]
+        #[allow(clippy::cast_possible_truncation)]
+        pub fn mean<I: IntoIterator<Item = Self>>(values: I) -> Self {
+                let (sum, count) = values.into_iter().fold((0i64, 0usize), |(sum, count), value| (sum + value.0, count + 1));
+
+                if count == 0 {
+                        return Self(0);
+                }
+
+                Self((sum as f64 / count as f64).round() as i64)
+        }
+
+        // Official track value, rounded from the unrounded channel values
+        #[allow(clippy::cast_precision_loss)]
+        #[allow(clippy::cast_possible_truncation)]
+        pub fn from_channels<I: IntoIterator<Item = Decibel>>(values: I) -> Self {
+                let (sum, count) = values.into_iter().fold((0.0, 0usize), |(sum, count), value| (sum + value.get_value(), count + 1));
+
+                if count == 0 {
+                        return Self(0);
+                }
+
+                Self((sum / count as f64).round() as i64)
+        }
+
+        // Official value of one channel, negative when the second peak sits below the loudest RMS
+        #[allow(clippy::cast_possible_truncation)]
+        #[must_use]
+        pub fn rounded(value: Decibel) -> Self {
+                Self(value.get_value().round() as i64)
+        }
+
+        #[must_use]
         pub fn get_string_value(self) -> String {
                 format!("{}", self.0)
         }
 
+        #[must_use]
         pub fn get_unit() -> String {
                 "DR".to_owned()
         }
@@ -46,14 +79,38 @@ mod tests {
     fn from_negative_decibel() {
         let db = Decibel::new(0.1);
         let dr: DynamicRange = db.into();
-        assert_eq!(dr.get_string_value(), "20");
+        assert_eq!(dr.get_string_value(), "-20");
     }
 
     #[test]
     fn from_decibel_rounds() {
         let db = Decibel::new(0.316_227_766_016_837_94);
         let dr: DynamicRange = db.into();
-        assert_eq!(dr.get_string_value(), "10");
+        assert_eq!(dr.get_string_value(), "-10");
+    }
+
+    #[test]
+    fn mean_is_rounded() {
+        assert_eq!(DynamicRange::mean([DynamicRange(8), DynamicRange(9)]).get_string_value(), "9");
+        assert_eq!(DynamicRange::mean([DynamicRange(8), DynamicRange(8), DynamicRange(9)]).get_string_value(), "8");
+        assert_eq!(DynamicRange::mean([]).get_string_value(), "0");
+    }
+
+    #[test]
+    fn channels_are_rounded_once() {
+        let channels = [8.4, 8.4, 9.3].map(|value: f64| Decibel::new(10f64.powf(value / 20.0)));
+
+        assert_eq!(DynamicRange::from_channels(channels).get_string_value(), "9");
+        assert_eq!(DynamicRange::mean(channels.map(DynamicRange::from)).get_string_value(), "8");
+        assert_eq!(DynamicRange::from_channels([]).get_string_value(), "0");
+    }
+
+    #[test]
+    fn negative_values_keep_their_sign() {
+        let square_wave = Decibel::new(2f64.sqrt().recip());
+
+        assert_eq!(DynamicRange::rounded(square_wave).get_string_value(), "-3");
+        assert_eq!(DynamicRange::from_channels([square_wave, square_wave]).get_string_value(), "-3");
     }
 
     #[test]
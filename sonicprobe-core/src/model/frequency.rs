@@ -2,14 +2,9 @@ use serde::{Serialize, Serializer};
 
 use crate::model::sonicprobe_error::SonicProbeError;
 
-#[derive(Clone, Copy)]
-pub enum Frequency {
-    CdQuality,
-    ProAudio,
-    HiResDouble,
-    DvdAudio,
-    UltraHiRes,
-    StudioMaster,
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Frequency {
+    hz: u32,
 }
 
 impl Serialize for Frequency {
@@ -17,58 +12,65 @@ impl Serialize for Frequency {
     where
         S: Serializer,
     {
-        serializer.serialize_u64(self.to_hz() as u64)
+        serializer.serialize_u32(self.hz)
     }
 }
 
 impl From<Frequency> for f64 {
     fn from(frequency: Frequency) -> Self {
-        match frequency {
-            Frequency::CdQuality => 44100.0,
-            Frequency::ProAudio => 48000.0,
-            Frequency::HiResDouble => 88200.0,
-            Frequency::DvdAudio => 96000.0,
-            Frequency::UltraHiRes => 176_400.0,
-            Frequency::StudioMaster => 192_000.0,
-        }
+        Self::from(frequency.hz)
     }
 }
 
 impl Frequency {
+    pub const CD_QUALITY: Self = Self { hz: 44100 };
+    pub const PRO_AUDIO: Self = Self { hz: 48000 };
+    pub const HI_RES_DOUBLE: Self = Self { hz: 88200 };
+    pub const DVD_AUDIO: Self = Self { hz: 96000 };
+    pub const ULTRA_HI_RES: Self = Self { hz: 176_400 };
+    pub const STUDIO_MASTER: Self = Self { hz: 192_000 };
+
+    /// # Errors
+    ///
+    /// Fails when `value` is 0 Hz.
     pub fn new(value: u32) -> Result<Self, SonicProbeError> {
-        match value {
-            44100 => Ok(Self::CdQuality),
-            48000 => Ok(Self::ProAudio),
-            88200 => Ok(Self::HiResDouble),
-            96000 => Ok(Self::DvdAudio),
-            176_400 => Ok(Self::UltraHiRes),
-            192_000 => Ok(Self::StudioMaster),
-            _ => Err(SonicProbeError {
+        if value == 0 {
+            return Err(SonicProbeError {
                 location: format!("{}:{}", file!(), line!()),
-                message: format!("Currently {value}Hz sample rates is not supported"),
-            }),
+                message: "sample rate must be greater than 0Hz".to_owned(),
+            });
         }
-    }
 
-    pub const fn to_hz(self) -> usize {
-        match self {
-            Self::CdQuality => 44100,
-            Self::ProAudio => 48000,
-            Self::HiResDouble => 88200,
-            Self::DvdAudio => 96000,
-            Self::UltraHiRes => 176_400,
-            Self::StudioMaster => 192_000,
-        }
+        Ok(Self { hz: value })
     }
 
-    pub const fn description(self) -> &'static str {
-        match self {
-            Self::CdQuality => "44.1 kHz - Standard for consumer audio",
-            Self::ProAudio => "48 kHz - Industry standard for video/broadcast",
-            Self::HiResDouble => "88.2 kHz - 2x CD rate for professional recording",
-            Self::DvdAudio => "96 kHz - High-resolution consumer format",
-            Self::UltraHiRes => "176.4 kHz - 4x CD rate for mastering",
-            Self::StudioMaster => "192 kHz - Highest professional standard",
+    #[must_use]
+    pub const fn to_hz(self) -> usize {
+        self.hz as usize
+    }
+
+    #[must_use]
+    pub fn description(self) -> String {
+        let known = match self.hz {
+            8000 => "8 kHz - Telephony",
+            16000 => "16 kHz - Wideband speech",
+            22050 => "22.05 kHz - Half CD rate",
+            32000 => "32 kHz - Broadcast and DAT long play",
+            44100 => "44.1 kHz - Standard for consumer audio",
+            48000 => "48 kHz - Industry standard for video/broadcast",
+            88200 => "88.2 kHz - 2x CD rate for professional recording",
+            96000 => "96 kHz - High-resolution consumer format",
+            176_400 => "176.4 kHz - 4x CD rate for mastering",
+            192_000 => "192 kHz - Highest professional standard",
+            352_800 => "352.8 kHz - DXD",
+            384_000 => "384 kHz - DXD",
+            _ => "",
+        };
+
+        if known.is_empty() {
+            format!("{} kHz", f64::from(self.hz) / 1000.0)
+        } else {
+            known.to_owned()
         }
     }
 }
@@ -81,168 +83,187 @@ mod tests {
 
     #[test]
     fn new_cd_quality() {
-        assert!(matches!(Frequency::new(44100).unwrap(), Frequency::CdQuality));
+        assert_eq!(Frequency::new(44100).unwrap(), Frequency::CD_QUALITY);
     }
 
     #[test]
     fn new_pro_audio() {
-        assert!(matches!(Frequency::new(48000).unwrap(), Frequency::ProAudio));
+        assert_eq!(Frequency::new(48000).unwrap(), Frequency::PRO_AUDIO);
     }
 
     #[test]
     fn new_hi_res_double() {
-        assert!(matches!(Frequency::new(88200).unwrap(), Frequency::HiResDouble));
+        assert_eq!(Frequency::new(88200).unwrap(), Frequency::HI_RES_DOUBLE);
     }
 
     #[test]
     fn new_dvd_audio() {
-        assert!(matches!(Frequency::new(96000).unwrap(), Frequency::DvdAudio));
+        assert_eq!(Frequency::new(96000).unwrap(), Frequency::DVD_AUDIO);
     }
 
     #[test]
     fn new_ultra_hi_res() {
-        assert!(matches!(Frequency::new(176_400).unwrap(), Frequency::UltraHiRes));
+        assert_eq!(Frequency::new(176_400).unwrap(), Frequency::ULTRA_HI_RES);
     }
 
     #[test]
     fn new_studio_master() {
-        assert!(matches!(Frequency::new(192_000).unwrap(), Frequency::StudioMaster));
+        assert_eq!(Frequency::new(192_000).unwrap(), Frequency::STUDIO_MASTER);
+    }
+
+    #[test]
+    fn new_arbitrary_rates() {
+        assert_eq!(Frequency::new(22050).unwrap().to_hz(), 22050);
+        assert_eq!(Frequency::new(8000).unwrap().to_hz(), 8000);
+        assert_eq!(Frequency::new(384_000).unwrap().to_hz(), 384_000);
     }
 
     #[test]
     fn new_unsupported() {
-        assert!(Frequency::new(22050).is_err());
+        assert!(Frequency::new(0).is_err());
     }
 
     #[test]
     fn to_hz_cd_quality() {
-        assert_eq!(Frequency::CdQuality.to_hz(), 44100);
+        assert_eq!(Frequency::CD_QUALITY.to_hz(), 44100);
     }
 
     #[test]
     fn to_hz_pro_audio() {
-        assert_eq!(Frequency::ProAudio.to_hz(), 48000);
+        assert_eq!(Frequency::PRO_AUDIO.to_hz(), 48000);
     }
 
     #[test]
     fn to_hz_hi_res_double() {
-        assert_eq!(Frequency::HiResDouble.to_hz(), 88200);
+        assert_eq!(Frequency::HI_RES_DOUBLE.to_hz(), 88200);
     }
 
     #[test]
     fn to_hz_dvd_audio() {
-        assert_eq!(Frequency::DvdAudio.to_hz(), 96000);
+        assert_eq!(Frequency::DVD_AUDIO.to_hz(), 96000);
     }
 
     #[test]
     fn to_hz_ultra_hi_res() {
-        assert_eq!(Frequency::UltraHiRes.to_hz(), 176_400);
+        assert_eq!(Frequency::ULTRA_HI_RES.to_hz(), 176_400);
     }
 
     #[test]
     fn to_hz_studio_master() {
-        assert_eq!(Frequency::StudioMaster.to_hz(), 192_000);
+        assert_eq!(Frequency::STUDIO_MASTER.to_hz(), 192_000);
     }
 
     #[test]
     fn description_cd_quality() {
-        assert_eq!(Frequency::CdQuality.description(), "44.1 kHz - Standard for consumer audio");
+        assert_eq!(Frequency::CD_QUALITY.description(), "44.1 kHz - Standard for consumer audio");
     }
 
     #[test]
     fn description_pro_audio() {
-        assert_eq!(Frequency::ProAudio.description(), "48 kHz - Industry standard for video/broadcast");
+        assert_eq!(Frequency::PRO_AUDIO.description(), "48 kHz - Industry standard for video/broadcast");
     }
 
     #[test]
     fn description_hi_res_double() {
-        assert_eq!(Frequency::HiResDouble.description(), "88.2 kHz - 2x CD rate for professional recording");
+        assert_eq!(Frequency::HI_RES_DOUBLE.description(), "88.2 kHz - 2x CD rate for professional recording");
     }
 
     #[test]
     fn description_dvd_audio() {
-        assert_eq!(Frequency::DvdAudio.description(), "96 kHz - High-resolution consumer format");
+        assert_eq!(Frequency::DVD_AUDIO.description(), "96 kHz - High-resolution consumer format");
     }
 
     #[test]
     fn description_ultra_hi_res() {
-        assert_eq!(Frequency::UltraHiRes.description(), "176.4 kHz - 4x CD rate for mastering");
+        assert_eq!(Frequency::ULTRA_HI_RES.description(), "176.4 kHz - 4x CD rate for mastering");
     }
 
     #[test]
     fn description_studio_master() {
-        assert_eq!(Frequency::StudioMaster.description(), "192 kHz - Highest professional standard");
+        assert_eq!(Frequency::STUDIO_MASTER.description(), "192 kHz - Highest professional standard");
+    }
+
+    #[test]
+    fn description_unknown_rate() {
+        assert_eq!(Frequency::new(11025).unwrap().description(), "11.025 kHz");
+        assert_eq!(Frequency::new(24000).unwrap().description(), "24 kHz");
     }
 
     #[test]
     fn serialize_cd_quality() {
-        let json = serde_json::to_string(&Frequency::CdQuality).unwrap();
+        let json = serde_json::to_string(&Frequency::CD_QUALITY).unwrap();
         assert_eq!(json, "44100");
     }
 
     #[test]
     fn serialize_pro_audio() {
-        let json = serde_json::to_string(&Frequency::ProAudio).unwrap();
+        let json = serde_json::to_string(&Frequency::PRO_AUDIO).unwrap();
         assert_eq!(json, "48000");
     }
 
     #[test]
     fn serialize_hi_res_double() {
-        let json = serde_json::to_string(&Frequency::HiResDouble).unwrap();
+        let json = serde_json::to_string(&Frequency::HI_RES_DOUBLE).unwrap();
         assert_eq!(json, "88200");
     }
 
     #[test]
     fn serialize_dvd_audio() {
-        let json = serde_json::to_string(&Frequency::DvdAudio).unwrap();
+        let json = serde_json::to_string(&Frequency::DVD_AUDIO).unwrap();
         assert_eq!(json, "96000");
     }
 
     #[test]
     fn serialize_ultra_hi_res() {
-        let json = serde_json::to_string(&Frequency::UltraHiRes).unwrap();
+        let json = serde_json::to_string(&Frequency::ULTRA_HI_RES).unwrap();
         assert_eq!(json, "176400");
     }
 
     #[test]
     fn serialize_studio_master() {
-        let json = serde_json::to_string(&Frequency::StudioMaster).unwrap();
+        let json = serde_json::to_string(&Frequency::STUDIO_MASTER).unwrap();
         assert_eq!(json, "192000");
     }
 
+    #[test]
+    fn serialize_arbitrary_rate() {
+        let json = serde_json::to_string(&Frequency::new(16000).unwrap()).unwrap();
+        assert_eq!(json, "16000");
+    }
+
     #[test]
     fn from_cd_quality() {
-        let f: f64 = Frequency::CdQuality.into();
+        let f: f64 = Frequency::CD_QUALITY.into();
         assert_eq!(f, 44100.0);
     }
 
     #[test]
     fn from_pro_audio() {
-        let f: f64 = Frequency::ProAudio.into();
+        let f: f64 = Frequency::PRO_AUDIO.into();
         assert_eq!(f, 48000.0);
     }
 
     #[test]
     fn from_hi_res_double() {
-        let f: f64 = Frequency::HiResDouble.into();
+        let f: f64 = Frequency::HI_RES_DOUBLE.into();
         assert_eq!(f, 88200.0);
     }
 
     #[test]
     fn from_dvd_audio() {
-        let f: f64 = Frequency::DvdAudio.into();
+        let f: f64 = Frequency::DVD_AUDIO.into();
         assert_eq!(f, 96000.0);
     }
 
     #[test]
     fn from_ultra_hi_res() {
-        let f: f64 = Frequency::UltraHiRes.into();
+        let f: f64 = Frequency::ULTRA_HI_RES.into();
         assert_eq!(f, 176_400.0);
     }
 
     #[test]
     fn from_studio_master() {
-        let f: f64 = Frequency::StudioMaster.into();
+        let f: f64 = Frequency::STUDIO_MASTER.into();
         assert_eq!(f, 192_000.0);
     }
-}
\ No newline at end of file
+}
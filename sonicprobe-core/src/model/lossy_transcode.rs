@@ -0,0 +1,70 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscodeVerdict {
+    Lossless,
+    Suspicious,
+    Transcoded,
+    Inconclusive,
+}
+
+// Outcome of the lossy transcode check, the spectral counterpart of the bit depth usage.
+// `cutoff` is the lowpass frequency in Hz when one was found, `persistence` the share of
+// the frames that keep it and `holes` the share of empty bands right below it
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LossyTranscode {
+    pub verdict: TranscodeVerdict,
+    pub confidence: f64,
+    pub cutoff: Option<f64>,
+    pub persistence: f64,
+    pub holes: f64,
+}
+
+impl LossyTranscode {
+    pub const INCONCLUSIVE: Self = Self {
+        verdict: TranscodeVerdict::Inconclusive,
+        confidence: 0.0,
+        cutoff: None,
+        persistence: 0.0,
+        holes: 0.0,
+    };
+
+    #[must_use]
+    pub fn description(&self) -> String {
+        let verdict = match self.verdict {
+            TranscodeVerdict::Lossless => "No lossy source",
+            TranscodeVerdict::Suspicious => "Possible lossy source",
+            TranscodeVerdict::Transcoded => "Lossy source",
+            TranscodeVerdict::Inconclusive => return "Inconclusive".to_owned(),
+        };
+
+        let confidence = format!("{:.0}% confidence", self.confidence * 100.0);
+        self.cutoff.map_or_else(
+            || format!("{verdict} ({confidence})"),
+            |cutoff| format!("{verdict} ({confidence}, cutoff {:.1} kHz)", cutoff / 1000.0),
+        )
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn description() {
+        let transcode =
+            LossyTranscode { verdict: TranscodeVerdict::Transcoded, confidence: 0.914, cutoff: Some(16_000.0), persistence: 1.0, holes: 0.1 };
+
+        assert_eq!(transcode.description(), "Lossy source (91% confidence, cutoff 16.0 kHz)");
+        assert_eq!(LossyTranscode::INCONCLUSIVE.description(), "Inconclusive");
+    }
+
+    #[test]
+    fn serialize() {
+        let json = serde_json::to_string(&LossyTranscode::INCONCLUSIVE).unwrap();
+
+        assert_eq!(json, r#"{"verdict":"inconclusive","confidence":0.0,"cutoff":null,"persistence":0.0,"holes":0.0}"#);
+    }
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+
+use crate::model::decibel::Decibel;
+
+#[derive(Clone, Copy, Serialize)]
+pub struct Loudness(f64);
+
+impl Loudness {
+        #[must_use]
+        pub const fn new(lufs: f64) -> Self {
+                Self(lufs)
+        }
+
+        #[must_use]
+        pub const fn get_value(self) -> f64 {
+                self.0
+        }
+
+        #[must_use]
+        pub fn get_string_value(self) -> String {
+                if self.0.is_finite() {
+                        format!("{:.1}", self.0)
+                } else {
+                        "-inf".to_owned()
+                }
+        }
+
+        #[must_use]
+        pub fn get_unit() -> String {
+                "LUFS".to_owned()
+        }
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct LoudnessRange(f64);
+
+impl LoudnessRange {
+        #[must_use]
+        pub const fn new(lu: f64) -> Self {
+                Self(lu)
+        }
+
+        #[must_use]
+        pub const fn get_value(self) -> f64 {
+                self.0
+        }
+
+        #[must_use]
+        pub fn get_string_value(self) -> String {
+                format!("{:.1}", self.0)
+        }
+
+        #[must_use]
+        pub fn get_unit() -> String {
+                "LU".to_owned()
+        }
+}
+
+// Momentary (400 ms) and short-term (3 s) loudness of the windows ending at `time` seconds,
+// each with the sample and true peak of the same window
+#[derive(Clone, Copy, Serialize)]
+pub struct LoudnessPoint {
+    pub time: f64,
+    pub momentary: Loudness,
+    pub peak: Decibel,
+    pub true_peak: Decibel,
+    pub short_term: Option<Loudness>,
+    pub short_term_peak: Option<Decibel>,
+    pub short_term_true_peak: Option<Decibel>,
+    pub psr: Option<f64>,
+}
+
+// PLR is the true peak against the integrated loudness, PSR the true peak of each 3 s window against
+// its short-term loudness, summarised by its minimum and median. Unmeasurable values are not finite
+#[derive(Clone, Copy, Serialize)]
+pub struct PeakToLoudness {
+    pub plr: f64,
+    pub min_psr: f64,
+    pub median_psr: f64,
+}
+
+impl PeakToLoudness {
+    pub fn new(true_peak: Decibel, integrated: Loudness, psr: &[f64]) -> Self {
+        let mut finite: Vec<f64> = psr.iter().copied().filter(|value| value.is_finite()).collect();
+        finite.sort_by(f64::total_cmp);
+
+        let middle = finite.len() / 2;
+        let median_psr = match finite.len() {
+            0 => f64::NAN,
+            length if length % 2 == 0 => f64::midpoint(finite[middle - 1], finite[middle]),
+            _ => finite[middle],
+        };
+
+        Self {
+            plr: true_peak.get_value() - integrated.get_value(),
+            min_psr: finite.first().copied().unwrap_or(f64::NAN),
+            median_psr,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_string_value() {
+        assert_eq!(Loudness::new(-23.04).get_string_value(), "-23.0");
+        assert_eq!(Loudness::new(f64::NEG_INFINITY).get_string_value(), "-inf");
+    }
+
+    #[test]
+    fn range_string_value() {
+        assert_eq!(LoudnessRange::new(7.26).get_string_value(), "7.3");
+        assert_eq!(LoudnessRange::get_unit(), "LU");
+    }
+
+    #[test]
+    fn serialize() {
+        assert_eq!(serde_json::to_string(&Loudness::new(-14.5)).unwrap(), "-14.5");
+        assert_eq!(serde_json::to_string(&Loudness::new(f64::NEG_INFINITY)).unwrap(), "null");
+    }
+
+    #[test]
+    fn peak_to_loudness() {
+        let ratios = PeakToLoudness::new(Decibel::new(0.5), Loudness::new(-14.0), &[9.0, f64::NAN, 6.0, 8.0, 12.0]);
+
+        assert!((ratios.plr - 7.98).abs() < 0.01);
+        assert_eq!(ratios.min_psr, 6.0);
+        assert_eq!(ratios.median_psr, 8.5);
+        assert_eq!(PeakToLoudness::new(Decibel::new(1.0), Loudness::new(-9.0), &[7.0, 5.0, 6.0]).median_psr, 6.0);
+    }
+
+    #[test]
+    fn silence_has_no_peak_to_loudness() {
+        let ratios = PeakToLoudness::new(Decibel::new(0.0), Loudness::new(f64::NEG_INFINITY), &[f64::NAN]);
+
+        assert!(ratios.plr.is_nan());
+        assert!(ratios.min_psr.is_nan());
+        assert_eq!(serde_json::to_string(&ratios).unwrap(), r#"{"plr":null,"min_psr":null,"median_psr":null}"#);
+    }
+}
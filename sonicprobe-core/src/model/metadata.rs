@@ -0,0 +1,64 @@
+use serde::Serialize;
+
+#[derive(Clone, Default, Serialize)]
+pub struct Metadata {
+    pub tags: Vec<Tag>,
+    pub markers: Vec<Marker>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Tag {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Marker {
+    pub id: u16,
+    pub position: u64,
+    pub name: String,
+}
+
+impl Metadata {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.markers.is_empty()
+    }
+
+    pub fn add_tag(&mut self, key: &str, value: String) {
+        self.tags.push(Tag {
+            key: key.to_owned(),
+            value,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_is_empty() {
+        assert!(Metadata::default().is_empty());
+    }
+
+    #[test]
+    fn add_tag() {
+        let mut metadata = Metadata::default();
+        metadata.add_tag("Title", "Song".to_owned());
+
+        assert!(!metadata.is_empty());
+        assert_eq!(metadata.tags[0].key, "Title");
+        assert_eq!(metadata.tags[0].value, "Song");
+    }
+
+    #[test]
+    fn markers_are_not_empty() {
+        let metadata = Metadata {
+            tags: Vec::new(),
+            markers: vec![Marker { id: 1, position: 0, name: "Start".to_owned() }],
+        };
+
+        assert!(!metadata.is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::model::{decibel::Decibel, loudness::Loudness};
+
+// ReplayGain 2.0 targets -18 LUFS measured with ITU-R BS.1770
+pub const REFERENCE_LOUDNESS: f64 = -18.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReplayGainPeak {
+    Sample,
+    True,
+}
+
+#[derive(Clone, Copy)]
+pub struct ReplayGain {
+    gain: f64,
+    peak: f64,
+}
+
+impl ReplayGain {
+        // None for programs too quiet to pass the absolute gate, they have no meaningful gain
+        #[must_use]
+        pub fn new(loudness: Loudness, peak: Decibel) -> Option<Self> {
+                if !loudness.get_value().is_finite() {
+                        return None;
+                }
+
+                Some(Self {
+                        gain: REFERENCE_LOUDNESS - loudness.get_value(),
+                        peak: 10f64.powf(peak.get_value() / 20.0),
+                })
+        }
+
+        #[must_use]
+        pub const fn gain(self) -> f64 {
+                self.gain
+        }
+
+        #[must_use]
+        pub const fn peak(self) -> f64 {
+                self.peak
+        }
+
+        // Value of REPLAYGAIN_TRACK_GAIN / REPLAYGAIN_ALBUM_GAIN, e.g. "-6.52 dB"
+        #[must_use]
+        pub fn gain_tag(self) -> String {
+                // Adding 0.0 turns a rounded -0.00 into 0.00
+                format!("{:.2} dB", (self.gain * 100.0).round() / 100.0 + 0.0)
+        }
+
+        // Value of REPLAYGAIN_TRACK_PEAK / REPLAYGAIN_ALBUM_PEAK, linear full scale
+        #[must_use]
+        pub fn peak_tag(self) -> String {
+                format!("{:.6}", self.peak)
+        }
+}
+
+impl Serialize for ReplayGain {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ReplayGain", 2)?;
+        state.serialize_field("gain", &self.gain_tag())?;
+        state.serialize_field("peak", &self.peak_tag())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gain_is_relative_to_reference() {
+        let replay_gain = ReplayGain::new(Loudness::new(-11.48), Decibel::new(0.5)).unwrap();
+
+        assert_eq!(replay_gain.gain_tag(), "-6.52 dB");
+        assert_eq!(replay_gain.peak_tag(), "0.500000");
+        assert_eq!(ReplayGain::new(Loudness::new(-23.0), Decibel::new(1.0)).unwrap().gain_tag(), "5.00 dB");
+    }
+
+    #[test]
+    fn negative_zero_is_not_written() {
+        let replay_gain = ReplayGain::new(Loudness::new(-17.999), Decibel::new(1.0)).unwrap();
+
+        assert_eq!(replay_gain.gain_tag(), "0.00 dB");
+    }
+
+    #[test]
+    fn silence_has_no_gain() {
+        assert!(ReplayGain::new(Loudness::new(f64::NEG_INFINITY), Decibel::new(0.0)).is_none());
+    }
+
+    #[test]
+    fn serialized_as_tag_values() {
+        let replay_gain = ReplayGain::new(Loudness::new(-14.0), Decibel::new(0.25)).unwrap();
+
+        assert_eq!(serde_json::to_string(&replay_gain).unwrap(), r#"{"gain":"-4.00 dB","peak":"0.250000"}"#);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{any::Any, fmt, num::TryFromIntError};
+use std::{any::Any, fmt, io, num::TryFromIntError};
 
 pub struct SonicProbeError {
     pub message: String,
@@ -49,11 +49,11 @@ impl From<TryFromIntError> for SonicProbeError {
     }
 }
 
-impl From<claxon::Error> for SonicProbeError {
-    fn from(error: claxon::Error) -> Self {
+impl From<io::Error> for SonicProbeError {
+    fn from(error: io::Error) -> Self {
         Self {
-            message: format!("Claxon error: {error}"),
-            location: "claxon::Error".to_owned(),
+            message: format!("I/O error: {error}"),
+            location: "std::io::Error".to_owned(),
         }
     }
 }
@@ -0,0 +1,165 @@
+use std::{f64::consts::PI, str::FromStr};
+
+use serde::Serialize;
+
+use crate::model::{channel_layout::ChannelLabel, sonicprobe_error::SonicProbeError};
+
+const DEFAULT_FFT_SIZE: usize = 4096;
+const MIN_FFT_SIZE: usize = 256;
+const MAX_FFT_SIZE: usize = 65536;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SpectrumWindow {
+    Rectangular,
+    Hann,
+    Hamming,
+    BlackmanHarris,
+}
+
+impl FromStr for SpectrumWindow {
+    type Err = SonicProbeError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "rectangular" => Ok(Self::Rectangular),
+            "hann" => Ok(Self::Hann),
+            "hamming" => Ok(Self::Hamming),
+            "blackman-harris" => Ok(Self::BlackmanHarris),
+            _ => Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: format!("unknown window '{value}', expected rectangular, hann, hamming or blackman-harris"),
+            }),
+        }
+    }
+}
+
+impl SpectrumWindow {
+    // Periodic form, the one that sums to a constant with 50% overlap
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn coefficients(self, size: usize) -> Vec<f64> {
+        (0..size)
+            .map(|index| {
+                let phase = 2.0 * PI * index as f64 / size as f64;
+                match self {
+                    Self::Rectangular => 1.0,
+                    Self::Hann => 0.5 - 0.5 * phase.cos(),
+                    Self::Hamming => 0.54 - 0.46 * phase.cos(),
+                    Self::BlackmanHarris => {
+                        0.358_75 - 0.488_29 * phase.cos() + 0.141_28 * (2.0 * phase).cos() - 0.011_68 * (3.0 * phase).cos()
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpectrumSettings {
+    fft_size: usize,
+    window: SpectrumWindow,
+}
+
+impl Default for SpectrumSettings {
+    fn default() -> Self {
+        Self { fft_size: DEFAULT_FFT_SIZE, window: SpectrumWindow::Hann }
+    }
+}
+
+impl SpectrumSettings {
+    /// # Errors
+    ///
+    /// Fails when `fft_size` is not a power of two between 256 and 65536.
+    pub fn new(fft_size: usize, window: SpectrumWindow) -> Result<Self, SonicProbeError> {
+        if !fft_size.is_power_of_two() || !(MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&fft_size) {
+            return Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: format!("FFT size must be a power of two between {MIN_FFT_SIZE} and {MAX_FFT_SIZE}, got {fft_size}"),
+            });
+        }
+
+        Ok(Self { fft_size, window })
+    }
+
+    #[must_use]
+    pub const fn fft_size(self) -> usize {
+        self.fft_size
+    }
+
+    #[must_use]
+    pub const fn window(self) -> SpectrumWindow {
+        self.window
+    }
+}
+
+// Level of a 1/3-octave band, `centre` is the nominal ISO frequency
+#[derive(Clone, Copy, Serialize)]
+pub struct SpectrumBand {
+    pub centre: f64,
+    pub level: f64,
+}
+
+// Long-term average spectrum of one channel, levels in dBFS where a full scale
+// sine reads 0 dBFS both in its bin and in its band. Empty when the channel is
+// shorter than one FFT frame, silent bins and bands are not finite
+#[derive(Clone, Serialize)]
+pub struct Spectrum {
+    pub channel: ChannelLabel,
+    pub fft_size: usize,
+    pub window: SpectrumWindow,
+    pub segments: usize,
+    pub bin_width: f64,
+    pub magnitudes: Vec<f64>,
+    pub bands: Vec<SpectrumBand>,
+}
+
+impl Spectrum {
+    #[must_use]
+    pub fn band_label(centre: f64) -> String {
+        let (value, unit) = if centre >= 1000.0 { (centre / 1000.0, "kHz") } else { (centre, "Hz") };
+        let value = format!("{value:.2}");
+
+        format!("{} {unit}", value.trim_end_matches('0').trim_end_matches('.'))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fft_size_must_be_a_power_of_two() {
+        assert_eq!(SpectrumSettings::new(8192, SpectrumWindow::Hamming).unwrap().fft_size(), 8192);
+        assert!(SpectrumSettings::new(3000, SpectrumWindow::Hann).is_err());
+        assert!(SpectrumSettings::new(128, SpectrumWindow::Hann).is_err());
+        assert!(SpectrumSettings::new(1 << 17, SpectrumWindow::Hann).is_err());
+    }
+
+    #[test]
+    fn parse_window() {
+        assert_eq!("Blackman-Harris".parse::<SpectrumWindow>().unwrap(), SpectrumWindow::BlackmanHarris);
+        assert!("kaiser".parse::<SpectrumWindow>().is_err());
+        assert_eq!(serde_json::to_string(&SpectrumWindow::BlackmanHarris).unwrap(), r#""blackman-harris""#);
+    }
+
+    #[test]
+    fn windows_overlap_to_a_constant() {
+        let hann = SpectrumWindow::Hann.coefficients(16);
+
+        assert_eq!(hann[0], 0.0);
+        assert!((hann[8] - 1.0).abs() < 1e-12);
+        assert!(hann[..8].iter().zip(&hann[8..]).all(|(a, b)| (a + b - 1.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn band_labels() {
+        assert_eq!(Spectrum::band_label(31.5), "31.5 Hz");
+        assert_eq!(Spectrum::band_label(250.0), "250 Hz");
+        assert_eq!(Spectrum::band_label(1000.0), "1 kHz");
+        assert_eq!(Spectrum::band_label(12500.0), "12.5 kHz");
+        assert_eq!(Spectrum::band_label(3150.0), "3.15 kHz");
+    }
+}
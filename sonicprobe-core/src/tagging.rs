@@ -0,0 +1,2 @@
+pub mod analysis_tags;
+pub mod flac_tags;
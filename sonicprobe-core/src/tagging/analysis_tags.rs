@@ -0,0 +1,109 @@
+use std::str::FromStr;
+
+use crate::model::{album::Album, audio_file::AudioFile, sonicprobe_error::SonicProbeError};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TagField {
+    ReplayGain,
+    DynamicRange,
+    Loudness,
+}
+
+impl FromStr for TagField {
+    type Err = SonicProbeError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "replaygain" => Ok(Self::ReplayGain),
+            "dr" => Ok(Self::DynamicRange),
+            "loudness" => Ok(Self::Loudness),
+            _ => Err(SonicProbeError {
+                location: format!("{}:{}", file!(), line!()),
+                message: format!("unknown tag field '{value}', expected replaygain, dr or loudness"),
+            }),
+        }
+    }
+}
+
+// Field names follow the ReplayGain 2.0 specification and the foobar2000 DR meter,
+// album fields are only written when the file was analysed as part of an album
+#[must_use]
+pub fn analysis_tags(file: &AudioFile, album: Option<&Album>, fields: &[TagField]) -> Vec<(String, String)> {
+    let mut tags = Vec::new();
+    let mut add = |name: &str, value: String| tags.push((name.to_owned(), value));
+
+    for field in fields {
+        match field {
+            TagField::ReplayGain => {
+                if let Some(track) = file.replay_gain {
+                    add("REPLAYGAIN_TRACK_GAIN", track.gain_tag());
+                    add("REPLAYGAIN_TRACK_PEAK", track.peak_tag());
+                }
+                if let Some(album) = album.and_then(|album| album.replay_gain) {
+                    add("REPLAYGAIN_ALBUM_GAIN", album.gain_tag());
+                    add("REPLAYGAIN_ALBUM_PEAK", album.peak_tag());
+                }
+            }
+            TagField::DynamicRange => {
+                add("DYNAMIC RANGE", file.dr().get_string_value());
+                if let Some(album) = album {
+                    add("ALBUM DYNAMIC RANGE", album.dr.get_string_value());
+                }
+            }
+            TagField::Loudness => {
+                if file.integrated_loudness.get_value().is_finite() {
+                    add("INTEGRATED_LOUDNESS", format!("{} LUFS", file.integrated_loudness.get_string_value()));
+                }
+            }
+        }
+    }
+
+    tags
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::decoders::flac::test_utils::analyse_samples;
+    use crate::model::{builders::album_builder::album_from_tracks, replay_gain::ReplayGainPeak};
+
+    fn analyse() -> AudioFile {
+        let samples: Vec<i32> = (0..88200).map(|index| (index * 37) % 20000 - 10000).collect();
+        analyse_samples(&samples, 2, 44100, 16)
+    }
+
+    fn names(tags: &[(String, String)]) -> Vec<&str> {
+        tags.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_fields() {
+        assert_eq!("ReplayGain".parse::<TagField>().unwrap(), TagField::ReplayGain);
+        assert_eq!("dr".parse::<TagField>().unwrap(), TagField::DynamicRange);
+        assert!("peak".parse::<TagField>().is_err());
+    }
+
+    #[test]
+    fn album_fields_need_an_album() {
+        let track = analyse();
+        let album = album_from_tracks(&[&track, &track], ReplayGainPeak::Sample).unwrap();
+        let fields = [TagField::ReplayGain, TagField::DynamicRange, TagField::Loudness];
+
+        assert_eq!(
+            names(&analysis_tags(&track, None, &fields)),
+            ["REPLAYGAIN_TRACK_GAIN", "REPLAYGAIN_TRACK_PEAK", "DYNAMIC RANGE", "INTEGRATED_LOUDNESS"]
+        );
+        assert_eq!(
+            names(&analysis_tags(&track, Some(&album), &fields[..2])),
+            [
+                "REPLAYGAIN_TRACK_GAIN",
+                "REPLAYGAIN_TRACK_PEAK",
+                "REPLAYGAIN_ALBUM_GAIN",
+                "REPLAYGAIN_ALBUM_PEAK",
+                "DYNAMIC RANGE",
+                "ALBUM DYNAMIC RANGE"
+            ]
+        );
+    }
+}
@@ -0,0 +1,351 @@
+// Vorbis comments are written by rewriting the file: every other metadata block and the
+// audio frames are copied unchanged into a temporary file that then replaces the original
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    mem,
+    path::{Path, PathBuf},
+};
+
+use claxon::FlacReader;
+use md5::{Digest, Md5};
+
+use crate::model::sonicprobe_error::SonicProbeError;
+
+const FLAC_MARKER: &[u8; 4] = b"fLaC";
+const STREAMINFO: u8 = 0;
+const VORBIS_COMMENT: u8 = 4;
+const LAST_BLOCK_FLAG: u8 = 0x80;
+const MAX_BLOCK_LENGTH: usize = 0xFF_FFFF;
+const VENDOR: &str = "sonicprobe";
+
+struct MetadataBlock {
+    header: [u8; 4],
+    body: Vec<u8>,
+}
+
+impl MetadataBlock {
+    const fn block_type(&self) -> u8 {
+        self.header[0] & !LAST_BLOCK_FLAG
+    }
+
+    const fn is_last(&self) -> bool {
+        self.header[0] & LAST_BLOCK_FLAG != 0
+    }
+}
+
+/// Fields already present under one of the given names (case-insensitive) are replaced,
+/// the rest of the comments and the vendor string are kept in their original order
+///
+/// # Errors
+///
+/// Fails when the file cannot be read or written, is not a FLAC file, holds a malformed comment
+/// block, has no MD5 signature or audio that does not match it, or when the comments outgrow a
+/// metadata block. The original file is left untouched on failure.
+pub fn write_flac_tags<P: AsRef<Path>>(path: P, tags: &[(String, String)]) -> Result<(), SonicProbeError> {
+    let path = path.as_ref();
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut blocks = read_metadata_blocks(&mut reader)?;
+
+    verify_md5(path, &blocks[0].body)?;
+
+    // An existing comment block keeps its position, a new one goes right after STREAMINFO
+    if !blocks.iter().any(|block| block.block_type() == VORBIS_COMMENT) {
+        blocks.insert(1, MetadataBlock { header: [VORBIS_COMMENT, 0, 0, 0], body: Vec::new() });
+    }
+    let index = blocks.iter().position(|block| block.block_type() == VORBIS_COMMENT).unwrap_or(1);
+
+    let comments = updated_comments(&blocks[index].body, tags)?;
+
+    if comments.len() > MAX_BLOCK_LENGTH {
+        return Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "Vorbis comments do not fit in a FLAC metadata block".to_owned(),
+        });
+    }
+
+    let length = u32::try_from(comments.len())?.to_be_bytes();
+    blocks[index].header[1..].copy_from_slice(&length[1..]);
+    blocks[index].body = comments;
+
+    // Only the flag bits that are actually wrong change, untouched blocks keep their exact bytes
+    let last_index = blocks.len() - 1;
+    for (index, block) in blocks.iter_mut().enumerate() {
+        if block.is_last() != (index == last_index) {
+            block.header[0] ^= LAST_BLOCK_FLAG;
+        }
+    }
+
+    let temporary_path = temporary_path(path)?;
+    let result = write_flac(&temporary_path, &blocks, &mut reader)
+        .and_then(|()| fs::set_permissions(&temporary_path, fs::metadata(path)?.permissions()))
+        .and_then(|()| fs::rename(&temporary_path, path));
+
+    if let Err(error) = result {
+        let _ = fs::remove_file(&temporary_path);
+        return Err(error.into());
+    }
+
+    Ok(())
+}
+
+fn read_metadata_blocks<R: Read>(reader: &mut R) -> Result<Vec<MetadataBlock>, SonicProbeError> {
+    let mut marker = [0u8; 4];
+    reader.read_exact(&mut marker)?;
+
+    if &marker != FLAC_MARKER {
+        return Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "tags can only be written to FLAC files".to_owned(),
+        });
+    }
+
+    let mut blocks = Vec::new();
+    loop {
+        let mut header = [0u8; 4];
+        reader.read_exact(&mut header)?;
+
+        let length = usize::from(header[1]) << 16 | usize::from(header[2]) << 8 | usize::from(header[3]);
+        let mut body = vec![0u8; length];
+        reader.read_exact(&mut body)?;
+
+        let block = MetadataBlock { header, body };
+        let is_last = block.is_last();
+        blocks.push(block);
+
+        if is_last {
+            break;
+        }
+    }
+
+    if blocks[0].block_type() != STREAMINFO || blocks[0].body.len() < 34 {
+        return Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "FLAC stream does not start with a STREAMINFO block".to_owned(),
+        });
+    }
+
+    Ok(blocks)
+}
+
+// The signature covers the decoded samples, interleaved, little-endian and byte aligned
+fn verify_md5(path: &Path, streaminfo: &[u8]) -> Result<(), SonicProbeError> {
+    let expected = &streaminfo[18..34];
+
+    if expected.iter().all(|byte| *byte == 0) {
+        return Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "STREAMINFO has no MD5 signature to verify the audio against".to_owned(),
+        });
+    }
+
+    let mut reader = FlacReader::open(path).map_err(|error| from_claxon_error(&error))?;
+    let bytes_per_sample = reader.streaminfo().bits_per_sample.div_ceil(8) as usize;
+    let mut hasher = Md5::new();
+    let mut bytes = Vec::new();
+    let mut buffer = Vec::new();
+    let mut blocks = reader.blocks();
+
+    while let Some(block) = blocks.read_next_or_eof(mem::take(&mut buffer)).map_err(|error| from_claxon_error(&error))? {
+        bytes.clear();
+        for index in 0..block.duration() {
+            for channel in 0..block.channels() {
+                bytes.extend_from_slice(&block.sample(channel, index).to_le_bytes()[..bytes_per_sample]);
+            }
+        }
+        hasher.update(&bytes);
+        buffer = block.into_buffer();
+    }
+
+    if hasher.finalize().as_slice() != expected {
+        return Err(SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "decoded audio does not match the STREAMINFO MD5 signature".to_owned(),
+        });
+    }
+
+    Ok(())
+}
+
+fn updated_comments(body: &[u8], tags: &[(String, String)]) -> Result<Vec<u8>, SonicProbeError> {
+    let (vendor, comments) = if body.is_empty() {
+        (VENDOR.as_bytes(), Vec::new())
+    } else {
+        parse_comments(body).ok_or_else(|| SonicProbeError {
+            location: format!("{}:{}", file!(), line!()),
+            message: "malformed Vorbis comment block".to_owned(),
+        })?
+    };
+
+    let replaced = |comment: &[u8]| {
+        let name = comment.split(|byte| *byte == b'=').next().unwrap_or_default();
+        tags.iter().any(|(tag, _)| tag.as_bytes().eq_ignore_ascii_case(name))
+    };
+
+    let comments: Vec<Vec<u8>> = comments
+        .into_iter()
+        .filter(|comment| !replaced(comment))
+        .map(<[u8]>::to_vec)
+        .chain(tags.iter().map(|(name, value)| format!("{name}={value}").into_bytes()))
+        .collect();
+
+    let mut updated = Vec::new();
+    updated.extend_from_slice(&u32::try_from(vendor.len())?.to_le_bytes());
+    updated.extend_from_slice(vendor);
+    updated.extend_from_slice(&u32::try_from(comments.len())?.to_le_bytes());
+    for comment in comments {
+        updated.extend_from_slice(&u32::try_from(comment.len())?.to_le_bytes());
+        updated.extend_from_slice(&comment);
+    }
+
+    Ok(updated)
+}
+
+fn parse_comments(body: &[u8]) -> Option<(&[u8], Vec<&[u8]>)> {
+    let mut position = 0;
+    let read_u32 = |position: &mut usize| {
+        let value = u32::from_le_bytes(body.get(*position..*position + 4)?.try_into().ok()?);
+        *position += 4;
+        usize::try_from(value).ok()
+    };
+
+    let vendor_length = read_u32(&mut position)?;
+    let vendor = body.get(position..position + vendor_length)?;
+    position += vendor_length;
+
+    let count = read_u32(&mut position)?;
+    let mut comments = Vec::new();
+    for _ in 0..count {
+        let length = read_u32(&mut position)?;
+        comments.push(body.get(position..position + length)?);
+        position += length;
+    }
+
+    Some((vendor, comments))
+}
+
+// Next to the original so that the final rename stays on the same file system
+fn temporary_path(path: &Path) -> Result<PathBuf, SonicProbeError> {
+    let file_name = path.file_name().ok_or_else(|| SonicProbeError {
+        location: format!("{}:{}", file!(), line!()),
+        message: format!("{} is not a file", path.display()),
+    })?;
+
+    Ok(path.with_file_name(format!(".{}.sonicprobe-tmp", file_name.to_string_lossy())))
+}
+
+fn write_flac<R: Read>(path: &Path, blocks: &[MetadataBlock], audio: &mut R) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(FLAC_MARKER)?;
+    for block in blocks {
+        writer.write_all(&block.header)?;
+        writer.write_all(&block.body)?;
+    }
+    io::copy(audio, &mut writer)?;
+
+    writer.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()
+}
+
+fn from_claxon_error(error: &claxon::Error) -> SonicProbeError {
+    SonicProbeError {
+        location: format!("{}:{}", file!(), line!()),
+        message: format!("FLAC decoding error: {error}"),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::decoders::flac::test_utils::encode_flac;
+
+    const STREAMINFO_MD5: std::ops::Range<usize> = 26..42;
+
+    fn samples() -> Vec<i32> {
+        (0..20000).map(|index| (index * 37) % 20000 - 10000).collect()
+    }
+
+    fn signed_flac(samples: &[i32]) -> Vec<u8> {
+        let mut stream = encode_flac(samples, 2, 44100, 16);
+        let bytes: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()[..2].to_vec()).collect();
+        stream[STREAMINFO_MD5].copy_from_slice(&Md5::digest(&bytes));
+        stream
+    }
+
+    fn temporary_file(name: &str, content: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("sonicprobe-tags-{name}-{}.flac", std::process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn tags(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, value)| ((*name).to_owned(), (*value).to_owned())).collect()
+    }
+
+    fn read_tags(path: &Path) -> Vec<(String, String)> {
+        let reader = FlacReader::open(path).unwrap();
+        reader.tags().map(|(name, value)| (name.to_owned(), value.to_owned())).collect()
+    }
+
+    #[test]
+    fn comments_are_added_without_touching_audio() {
+        let original = signed_flac(&samples());
+        let path = temporary_file("added", &original);
+
+        write_flac_tags(&path, &tags(&[("REPLAYGAIN_TRACK_GAIN", "-4.20 dB")])).unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let audio_start = 4 + 4 + 34;
+        assert_eq!(written[..4], original[..4]);
+        assert_eq!(written[4], STREAMINFO);
+        assert_eq!(written[5..audio_start], original[5..audio_start]);
+        assert_eq!(written[audio_start], VORBIS_COMMENT | LAST_BLOCK_FLAG);
+        assert_eq!(written[written.len() - (original.len() - audio_start)..], original[audio_start..]);
+    }
+
+    #[test]
+    fn existing_fields_are_replaced() {
+        let path = temporary_file("replaced", &signed_flac(&samples()));
+
+        write_flac_tags(&path, &tags(&[("ARTIST", "Someone"), ("DYNAMIC RANGE", "9")])).unwrap();
+        write_flac_tags(&path, &tags(&[("Dynamic Range", "12")])).unwrap();
+        let written = read_tags(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(written, tags(&[("ARTIST", "Someone"), ("Dynamic Range", "12")]));
+    }
+
+    #[test]
+    fn mismatching_signature_is_refused() {
+        let mut stream = signed_flac(&samples());
+        stream[STREAMINFO_MD5.start] ^= 0xFF;
+        let path = temporary_file("mismatch", &stream);
+
+        let result = write_flac_tags(&path, &tags(&[("DYNAMIC RANGE", "9")]));
+        let unchanged = fs::read(&path).unwrap() == stream;
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+        assert!(unchanged);
+    }
+
+    #[test]
+    fn missing_signature_is_refused() {
+        let path = temporary_file("unsigned", &encode_flac(&samples(), 2, 44100, 16));
+
+        let result = write_flac_tags(&path, &tags(&[("DYNAMIC RANGE", "9")]));
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn malformed_comments_are_rejected() {
+        assert!(parse_comments(&[4, 0, 0, 0, b'a']).is_none());
+        assert!(updated_comments(&[1, 0, 0, 0, b'v', 1, 0, 0, 0], &[]).is_err());
+    }
+}